@@ -1,6 +1,9 @@
 use std::path;
 use std::sync::OnceLock;
 
+use crate::daemon;
+use crate::git_config::config_option_var;
+use crate::git_config::config_var;
 use crate::ilsore_format;
 use crate::ilsore_format_color;
 use crate::structs;
@@ -28,6 +31,22 @@ pub(crate) struct Args {
     #[arg(long, value_name = "REFERENCE", default_value = "HEAD")]
     pub git_reference: Option<String>,
 
+    /// Glob tags should match to describe detached HEAD
+    #[arg(long, value_name = "PATTERN")]
+    pub git_describe_pattern: Option<String>,
+
+    /// Minimal length of abbreviated commit hash. Default is core.abbrev
+    #[arg(long, value_name = "LENGTH", value_parser = clap::value_parser!(u64).range(4..=40))]
+    pub git_abbrev: Option<u64>,
+
+    /// Glob hosts of self-hosted git servers match, e.g. "git.*.example"
+    #[arg(long, value_name = "PATTERN")]
+    pub git_self_hosted_pattern: Option<String>,
+
+    /// Base reference to show drift from, "auto" detects origin/HEAD
+    #[arg(long, value_name = "REFERENCE")]
+    pub git_base_reference: Option<String>,
+
     /// Working directory to start to search for git information. Default is current folder
     #[arg(long, value_name = "FOLDER")]
     pub git_start_folder: Option<path::PathBuf>,
@@ -36,11 +55,16 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
     pub git_include_submodules: bool,
 
-    /// If git status should include untracked files
+    /// If git status should exclude untracked files, same as `--git-untracked-files no`
     #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
     pub git_exclude_untracked: bool,
 
-    /// If git status should softly refresh indices
+    /// How git status should report untracked files.
+    /// Default is status.showUntrackedFiles of the repository or "normal"
+    #[arg(long, value_name = "MODE", value_enum)]
+    pub git_untracked_files: Option<structs::UntrackedFiles>,
+
+    /// If git status should softly refresh indices, ignored with time budget
     #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
     pub git_refresh_status: bool,
 
@@ -52,6 +76,72 @@ pub(crate) struct Args {
     #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
     pub git_exclude_workdir_stats: bool,
 
+    /// Show numbers of inserted and deleted lines of uncommitted changes, it may be slow
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_include_diff_stats: bool,
+
+    /// Show number of stashes next to stash symbol
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_stash_count: bool,
+
+    /// Show number of files next to file status symbols
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_status_counts: bool,
+
+    /// Show number of commits next to ahead/behind symbols
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_ahead_behind_counts: bool,
+
+    /// Show how long ago HEAD commit was made
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_commit_age: bool,
+
+    /// Show initials of HEAD commit author
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_commit_author: bool,
+
+    /// Show hosting provider of tracked remote
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_remote: bool,
+
+    /// Show "owner/repo" of tracked remote next to its provider
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub git_remote_slug: bool,
+
+    /// Largest number shown in full, bigger numbers are shown as "LIMIT+"
+    #[arg(long, value_name = "LIMIT", default_value_t = 99)]
+    pub git_count_limit: usize,
+
+    /// Which part of reference name to show
+    #[arg(long, value_name = "DISPLAY", default_value_t, value_enum)]
+    pub git_reference_display: structs::ReferenceDisplay,
+
+    /// Longer reference names are shortened in the middle
+    #[arg(long, value_name = "LENGTH")]
+    pub git_reference_max_length: Option<usize>,
+
+    /// Time budget for git information in milliseconds
+    #[arg(long, value_name = "MILLISECONDS")]
+    pub git_timeout_ms: Option<u64>,
+
+    /// Socket of cache daemon in a folder private to the user.
+    /// Default is in XDG_RUNTIME_DIR or temporary folder
+    #[arg(long, value_name = "SOCKET")]
+    pub daemon_socket: Option<path::PathBuf>,
+
+    /// Don't ask cache daemon for git information
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub disable_daemon: bool,
+
+    /// How long cached git information is reused for unchanged repository.
+    /// Edits of working tree files show up once it expires
+    #[arg(long, value_name = "MILLISECONDS", default_value_t = 2000)]
+    pub cache_ttl_ms: u64,
+
+    /// Don't reuse file status and ahead/behind information from cache folder
+    #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
+    pub no_cache: bool,
+
     /// Last command exit status
     #[arg(long, value_name = "ERROR_CODE", default_value_t = 0)]
     pub last_exit_status: u8,
@@ -67,6 +157,15 @@ pub(crate) struct Args {
     /// Output errros for debugging purposes
     #[arg(long, default_value_t = false, action=clap::ArgAction::SetTrue)]
     pub error_output: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Run cache daemon for git information listening on daemon socket
+    Daemon,
 }
 
 #[derive(clap::ValueEnum, Clone)] // required for clap::ValueEnum
@@ -117,4 +216,80 @@ impl Args {
     pub fn theme(&self) -> ThemeFunction {
         THEME_NAMES.get().expect("Uninitialized theme names")[self.theme_name]
     }
+
+    /// Repository git config overrides command line arguments, e.g. `ilsore-format.theme-name`.
+    /// Keys are argument names without "git-" prefix, except negative flags which are stored
+    /// positively: `--no-cache` is `use-cache` and `--git-exclude-*` flags are `include-*`.
+    ///
+    /// Options of git information itself are overridden later by `git_utils`, time budget
+    /// is overridden here as well to limit waiting for daemon. Start folder, last exit status,
+    /// error output and daemon socket can't be overridden
+    pub fn override_from_config(&mut self, config: &git2::Config) {
+        let c = config;
+
+        self.static_hostname = config_option_var(c, "static-hostname", self.static_hostname.take());
+        self.disable_git = config_var(c, "disable-git", self.disable_git);
+        self.git_reference = config_option_var(c, "reference", self.git_reference.take());
+        self.git_timeout_ms = config_option_var(c, "timeout-ms", self.git_timeout_ms);
+        self.git_stash_count = config_var(c, "stash-count", self.git_stash_count);
+        self.git_status_counts = config_var(c, "status-counts", self.git_status_counts);
+        self.git_ahead_behind_counts =
+            config_var(c, "ahead-behind-counts", self.git_ahead_behind_counts);
+        self.git_commit_age = config_var(c, "commit-age", self.git_commit_age);
+        self.git_commit_author = config_var(c, "commit-author", self.git_commit_author);
+        self.git_remote = config_var(c, "remote", self.git_remote);
+        self.git_remote_slug = config_var(c, "remote-slug", self.git_remote_slug);
+        self.git_count_limit = config_var(c, "count-limit", self.git_count_limit);
+        self.git_reference_display = config_var(c, "reference-display", self.git_reference_display);
+        self.git_reference_max_length =
+            config_option_var(c, "reference-max-length", self.git_reference_max_length);
+        self.disable_daemon = config_var(c, "disable-daemon", self.disable_daemon);
+        self.cache_ttl_ms = config_var(c, "cache-ttl-ms", self.cache_ttl_ms);
+        self.theme_symbols = config_var(c, "theme-symbols", self.theme_symbols);
+        self.theme_name = config_var(c, "theme-name", self.theme_name);
+    }
+
+    pub fn daemon_socket(&self) -> path::PathBuf {
+        self.daemon_socket
+            .clone()
+            .unwrap_or_else(daemon::default_socket_path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Args, ThemeNames};
+    use crate::test_util::TestDir;
+    use clap::Parser;
+
+    #[test]
+    fn override_from_config_test() {
+        let folder = TestDir::new("args");
+        let file = folder.join("config");
+        std::fs::write(
+            &file,
+            "[ilsore-format]\n\
+             timeout-ms = 50\n\
+             theme-name = ilsore-no-color\n\
+             count-limit = nine\n",
+        )
+        .unwrap();
+        let config = git2::Config::open(&file).unwrap().snapshot().unwrap();
+
+        let mut args = Args::parse_from([
+            env!("CARGO_BIN_NAME"),
+            "--git-timeout-ms",
+            "500",
+            "--git-count-limit",
+            "9",
+            "--git-stash-count",
+        ]);
+        args.override_from_config(&config);
+
+        assert_eq!(args.git_timeout_ms, Some(50));
+        assert!(matches!(args.theme_name, ThemeNames::IlsoreNoColor));
+        // Invalid and missing values keep command line ones
+        assert_eq!(args.git_count_limit, 9);
+        assert!(args.git_stash_count);
+    }
 }
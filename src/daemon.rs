@@ -0,0 +1,462 @@
+use std::env;
+use std::path;
+
+use crate::user_host;
+
+pub(crate) fn default_socket_path() -> path::PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime_dir) => {
+            path::PathBuf::from(runtime_dir).join(format!("{}.sock", env!("CARGO_BIN_NAME")))
+        }
+        // Shared temporary folder gets a private subfolder, see `check_private_folder`
+        None => env::temp_dir()
+            .join(format!(
+                "{}-{}",
+                env!("CARGO_BIN_NAME"),
+                user_host::username().unwrap_or_default()
+            ))
+            .join(format!("{}.sock", env!("CARGO_BIN_NAME"))),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) use unix::{query, run};
+
+#[cfg(not(unix))]
+pub(crate) use other::{query, run};
+
+#[cfg(not(unix))]
+mod other {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use crate::error::Result;
+    use crate::structs;
+
+    pub(crate) fn run(_socket: &Path, _cache_ttl: Duration) -> Result<()> {
+        Err("cache daemon is supported on unix only".into())
+    }
+
+    pub(crate) fn query(
+        _socket: &Path,
+        _location: &structs::GitRepoLocation,
+        _options: &structs::GetGitInfoOptions,
+    ) -> Result<structs::GitOutputOptions> {
+        Err("cache daemon is supported on unix only".into())
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::io::Write;
+    use std::os::unix::fs::DirBuilderExt;
+    use std::os::unix::fs::MetadataExt;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixListener;
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use crate::error::MapLog;
+    use crate::error::Result;
+    use crate::git_cache;
+    use crate::git_utils;
+    use crate::structs;
+
+    /// How long client waits for daemon before computing information directly
+    const CLIENT_TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Percent of time budget client waits for daemon, the rest is left to compute directly
+    const CLIENT_BUDGET_PERCENT: u64 = 50;
+
+    /// Percent of time budget daemon computes for, leaving time to send the answer
+    const DAEMON_BUDGET_PERCENT: u64 = 40;
+
+    /// Query sent by client, one JSON object per line
+    #[derive(Debug, serde::Serialize, serde::Deserialize)]
+    struct Request {
+        location: structs::GitRepoLocation,
+        reference_name: String,
+        describe_pattern: Option<String>,
+        abbrev: Option<u64>,
+        self_hosted_pattern: Option<String>,
+        base_reference: Option<String>,
+        include_submodules: bool,
+        untracked_files: Option<structs::UntrackedFiles>,
+        refresh_status: bool,
+        include_ahead_behind: bool,
+        include_workdir_stats: bool,
+        include_diff_stats: bool,
+        timeout_ms: Option<u64>,
+        use_cache: bool,
+        cache_ttl_ms: u64,
+    }
+
+    impl Request {
+        fn new(location: &structs::GitRepoLocation, options: &structs::GetGitInfoOptions) -> Self {
+            Request {
+                location: location.clone(),
+                reference_name: options.reference_name.to_string(),
+                describe_pattern: options.describe_pattern.map(String::from),
+                abbrev: options.abbrev,
+                self_hosted_pattern: options.self_hosted_pattern.map(String::from),
+                base_reference: options.base_reference.map(String::from),
+                include_submodules: options.include_submodules,
+                untracked_files: options.untracked_files,
+                refresh_status: options.refresh_status,
+                include_ahead_behind: options.include_ahead_behind,
+                include_workdir_stats: options.include_workdir_stats,
+                include_diff_stats: options.include_diff_stats,
+                timeout_ms: options.timeout_ms,
+                use_cache: options.use_cache,
+                cache_ttl_ms: options.cache_ttl_ms,
+            }
+        }
+
+        fn options(&self) -> structs::GetGitInfoOptions<'_> {
+            structs::GetGitInfoOptions {
+                reference_name: &self.reference_name,
+                describe_pattern: self.describe_pattern.as_deref(),
+                abbrev: self.abbrev,
+                self_hosted_pattern: self.self_hosted_pattern.as_deref(),
+                base_reference: self.base_reference.as_deref(),
+                include_submodules: self.include_submodules,
+                untracked_files: self.untracked_files,
+                refresh_status: self.refresh_status,
+                include_ahead_behind: self.include_ahead_behind,
+                include_workdir_stats: self.include_workdir_stats,
+                include_diff_stats: self.include_diff_stats,
+                timeout_ms: self.timeout_ms,
+                daemon_socket: None,
+                use_cache: self.use_cache,
+                cache_ttl_ms: self.cache_ttl_ms,
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    struct CacheEntry {
+        fingerprint: git_cache::Fingerprint,
+        created: Instant,
+        /// Serialized `structs::GitOutputOptions`
+        response: String,
+    }
+
+    /// Cache entries by serialized request, expired ones are dropped by `fresh_entry`
+    type Cache = Mutex<HashMap<String, CacheEntry>>;
+
+    pub(crate) fn run(socket: &Path, cache_ttl: Duration) -> Result<()> {
+        let folder = socket_folder(socket);
+        if !folder.exists() {
+            fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(folder)?;
+        }
+        check_private_folder(socket)?;
+
+        if socket.exists() {
+            fs::remove_file(socket)?;
+        }
+
+        let listener = UnixListener::bind(socket)?;
+        let cache = Cache::default();
+
+        thread::scope(|s| {
+            for stream in listener.incoming() {
+                if let Some(stream) = stream.ok_or_log() {
+                    let cache = &cache;
+                    s.spawn(move || serve(stream, cache, cache_ttl).ok_or_log());
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn serve(mut stream: UnixStream, cache: &Cache, cache_ttl: Duration) -> Result<()> {
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+
+        let key = line.trim_end().to_string();
+        let request: Request = serde_json::from_str(&key)?;
+
+        // Changes inside submodules don't touch any file fingerprint looks at
+        let cacheable = !request.include_submodules;
+        let fresh = match cacheable {
+            true => fresh_entry(cache, &key, cache_ttl)?,
+            false => None,
+        };
+
+        let repo = git2::Repository::open(&request.location.git_dir)?;
+        let fingerprint = git_cache::Fingerprint::new(&repo)?;
+
+        let response = match fresh.filter(|e| e.fingerprint == fingerprint) {
+            Some(entry) => entry.response,
+            None => {
+                let result = git_utils::process_repo(&request.location, &request.options())?;
+                let response = serde_json::to_string(&result)?;
+
+                // Partial results are returned but not remembered
+                if cacheable && !result.timed_out {
+                    cache
+                        .lock()
+                        .map_err(|_| "daemon cache is poisoned")?
+                        .insert(
+                            key,
+                            CacheEntry {
+                                fingerprint,
+                                created: Instant::now(),
+                                response: response.clone(),
+                            },
+                        );
+                }
+                response
+            }
+        };
+
+        stream.write_all(response.as_bytes())?;
+        stream.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Entry of `key` younger than `cache_ttl`. Expired entries of every request are dropped,
+    /// so the daemon keeps only repositories asked for recently
+    fn fresh_entry(cache: &Cache, key: &str, cache_ttl: Duration) -> Result<Option<CacheEntry>> {
+        let mut entries = cache.lock().map_err(|_| "daemon cache is poisoned")?;
+        entries.retain(|_, e| e.created.elapsed() < cache_ttl);
+        Ok(entries.get(key).cloned())
+    }
+
+    pub(crate) fn query(
+        socket: &Path,
+        location: &structs::GitRepoLocation,
+        options: &structs::GetGitInfoOptions,
+    ) -> Result<structs::GitOutputOptions> {
+        // Anyone able to create the socket could put any text into the prompt
+        check_private_folder(socket)?;
+
+        let (client_timeout, daemon_timeout_ms) = budget_shares(options.timeout_ms);
+        let mut stream = UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(client_timeout))?;
+        stream.set_write_timeout(Some(client_timeout))?;
+
+        let request = Request {
+            timeout_ms: daemon_timeout_ms,
+            ..Request::new(location, options)
+        };
+        let mut request = serde_json::to_string(&request)?;
+        request.push('\n');
+        stream.write_all(request.as_bytes())?;
+
+        let mut response = String::new();
+        BufReader::new(&stream).read_line(&mut response)?;
+
+        Ok(serde_json::from_str(&response)?)
+    }
+
+    /// How long client waits for daemon and time budget daemon gets, both are parts of `timeout_ms`
+    fn budget_shares(timeout_ms: Option<u64>) -> (Duration, Option<u64>) {
+        match timeout_ms {
+            Some(timeout_ms) => (
+                // Zero timeout means blocking forever for sockets
+                Duration::from_millis((timeout_ms * CLIENT_BUDGET_PERCENT / 100).max(1)),
+                Some(timeout_ms * DAEMON_BUDGET_PERCENT / 100),
+            ),
+            None => (CLIENT_TIMEOUT, None),
+        }
+    }
+
+    #[inline]
+    fn socket_folder(socket: &Path) -> &Path {
+        socket
+            .parent()
+            .filter(|f| !f.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+    }
+
+    /// Other users must not be able to create the socket before daemon does
+    fn check_private_folder(socket: &Path) -> Result<()> {
+        let folder = socket_folder(socket);
+        let metadata = fs::metadata(folder)?;
+        // SAFETY: getuid has no preconditions and never fails
+        let owned = metadata.uid() == unsafe { libc::getuid() };
+
+        match (owned, metadata.permissions().mode() & 0o077) {
+            (true, 0) => Ok(()),
+            _ => Err(format!(
+                "folder '{}' of daemon socket isn't private to the user",
+                folder.display()
+            )
+            .into()),
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::{budget_shares, check_private_folder, fresh_entry, query, run};
+        use super::{Cache, CacheEntry};
+        use crate::git_cache;
+        use crate::structs;
+        use crate::test_util::TestDir;
+        use rstest::rstest;
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+        use std::path::Path;
+        use std::thread;
+        use std::time::Duration;
+        use std::time::Instant;
+
+        /// Empty folder for a test with given permissions
+        fn fixture_dir(name: &str, mode: u32) -> TestDir {
+            let path = TestDir::new(&format!("daemon-{}", name));
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+            path
+        }
+
+        fn options(timeout_ms: Option<u64>) -> structs::GetGitInfoOptions<'static> {
+            structs::GetGitInfoOptions {
+                reference_name: "HEAD",
+                describe_pattern: None,
+                abbrev: None,
+                self_hosted_pattern: None,
+                base_reference: None,
+                include_submodules: false,
+                untracked_files: None,
+                refresh_status: false,
+                include_ahead_behind: true,
+                include_workdir_stats: true,
+                include_diff_stats: false,
+                timeout_ms,
+                daemon_socket: None,
+                use_cache: false,
+                cache_ttl_ms: 60_000,
+            }
+        }
+
+        #[rstest]
+        #[case(None, Duration::from_millis(500), None)]
+        #[case(Some(100), Duration::from_millis(50), Some(40))]
+        #[case(Some(1), Duration::from_millis(1), Some(0))]
+        fn budget_shares_test(
+            #[case] timeout_ms: Option<u64>,
+            #[case] client_timeout: Duration,
+            #[case] daemon_timeout_ms: Option<u64>,
+        ) {
+            assert_eq!(
+                budget_shares(timeout_ms),
+                (client_timeout, daemon_timeout_ms)
+            );
+        }
+
+        #[rstest]
+        #[case("private", 0o700, true)]
+        #[case("shared", 0o755, false)]
+        #[case("sticky", 0o1777, false)]
+        fn check_private_folder_test(#[case] name: &str, #[case] mode: u32, #[case] ok: bool) {
+            let folder = fixture_dir(name, mode);
+            assert_eq!(
+                check_private_folder(&folder.join("daemon.sock")).is_ok(),
+                ok
+            );
+        }
+
+        #[test]
+        fn check_private_folder_owner_test() {
+            // Only root can give a folder away
+            if unsafe { libc::geteuid() } != 0 {
+                return;
+            }
+
+            let folder = fixture_dir("foreign", 0o700);
+            std::os::unix::fs::chown(&folder, Some(65534), None).unwrap();
+            assert!(check_private_folder(&folder.join("daemon.sock")).is_err());
+        }
+
+        #[test]
+        fn fresh_entry_drops_expired_test() {
+            let path = fixture_dir("expired", 0o700);
+            let repo = git2::Repository::init(path.join("repo")).unwrap();
+            let entry = |age: Duration| CacheEntry {
+                fingerprint: git_cache::Fingerprint::new(&repo).unwrap(),
+                created: Instant::now().checked_sub(age).unwrap(),
+                response: String::new(),
+            };
+
+            let cache = Cache::default();
+            cache
+                .lock()
+                .unwrap()
+                .insert("old".to_string(), entry(Duration::from_secs(120)));
+            cache
+                .lock()
+                .unwrap()
+                .insert("new".to_string(), entry(Duration::ZERO));
+
+            let ttl = Duration::from_secs(60);
+            assert!(fresh_entry(&cache, "old", ttl).unwrap().is_none());
+            assert!(fresh_entry(&cache, "new", ttl).unwrap().is_some());
+            assert_eq!(cache.lock().unwrap().len(), 1);
+        }
+
+        #[test]
+        fn query_shows_index_updates_test() {
+            let path = fixture_dir("query", 0o700);
+
+            let repo = git2::Repository::init(path.join("repo")).unwrap();
+            fs::write(path.join("repo/file.txt"), "a\n").unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+            let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+            let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "a", &tree, &[])
+                .unwrap();
+
+            let location = structs::GitRepoLocation {
+                git_dir: repo.path().to_path_buf(),
+                work_dir: repo.workdir().map(Path::to_path_buf),
+            };
+
+            let socket = path.join("daemon/daemon.sock");
+            let daemon_socket = socket.clone();
+            thread::spawn(move || run(&daemon_socket, Duration::from_secs(60)));
+            while !socket.exists() {
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            let staged = || {
+                query(&socket, &location, &options(Some(10_000)))
+                    .unwrap()
+                    .file_status
+                    .unwrap()
+                    .staged
+            };
+
+            assert_eq!(staged(), 0);
+            // Cached answer
+            assert_eq!(staged(), 0);
+
+            fs::write(path.join("repo/file.txt"), "a\nb\n").unwrap();
+            index.add_path(Path::new("file.txt")).unwrap();
+            index.write().unwrap();
+            assert_eq!(staged(), 1);
+
+            assert_eq!(
+                fs::metadata(path.join("daemon"))
+                    .unwrap()
+                    .permissions()
+                    .mode()
+                    & 0o777,
+                0o700
+            );
+        }
+    }
+}
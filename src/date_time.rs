@@ -1,9 +1,45 @@
 use crate::structs;
 
+/// Time passed since `time` in the largest whole unit, e.g. "3h" or "2d"
+pub(crate) fn relative_age(time: i64, now: chrono::DateTime<chrono::Utc>) -> String {
+    // Commits from the future because of clock skew are shown as fresh
+    let seconds = (now.timestamp() - time).max(0);
+    let age = chrono::TimeDelta::seconds(seconds);
+
+    match age {
+        a if a < chrono::TimeDelta::minutes(1) => format!("{}s", a.num_seconds()),
+        a if a < chrono::TimeDelta::hours(1) => format!("{}m", a.num_minutes()),
+        a if a < chrono::TimeDelta::days(1) => format!("{}h", a.num_hours()),
+        a if a < chrono::TimeDelta::weeks(1) => format!("{}d", a.num_days()),
+        a if a < chrono::TimeDelta::days(365) => format!("{}w", a.num_weeks()),
+        a => format!("{}y", a.num_days() / 365),
+    }
+}
+
 pub(crate) fn date_time() -> structs::DateTime {
     let dt: chrono::DateTime<chrono::Local> = chrono::Local::now();
-    return structs::DateTime {
+    structs::DateTime {
         date: Box::new(dt.format("%F")),
         time: Box::new(dt.format("%T")),
-    };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::relative_age;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(1_000_000, "0s")]
+    #[case(999_990, "10s")]
+    #[case(1_000_000 - 59 * 60, "59m")]
+    #[case(1_000_000 - 3 * 3600, "3h")]
+    #[case(1_000_000 - 2 * 86400 - 5, "2d")]
+    #[case(1_000_000 - 20 * 86400, "2w")]
+    #[case(1_000_000 - 800 * 86400, "2y")]
+    #[case(1_000_100, "0s")]
+    fn relative_age_test(#[case] time: i64, #[case] expected: &str) {
+        let now = chrono::DateTime::from_timestamp(1_000_000, 0).unwrap();
+        assert_eq!(relative_age(time, now), expected);
+    }
 }
@@ -11,6 +11,7 @@ pub(crate) static VERBOSE_ERRORS: OnceLock<bool> = OnceLock::new();
 pub(crate) enum Error {
     Io(std::io::Error),
     Git(git2::Error),
+    Json(serde_json::Error),
     Message(Cow<'static, str>),
 }
 
@@ -28,6 +29,12 @@ impl From<git2::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
 impl From<String> for Error {
     fn from(s: String) -> Self {
         Self::Message(s.into())
@@ -45,6 +52,7 @@ impl std::error::Error for Error {
         match self {
             Error::Io(err) => Some(err),
             Error::Git(err) => Some(err),
+            Error::Json(err) => Some(err),
             Error::Message(_) => None,
         }
     }
@@ -55,6 +63,7 @@ impl std::fmt::Display for Error {
         match self {
             Error::Io(err) => err.fmt(f),
             Error::Git(err) => err.fmt(f),
+            Error::Json(err) => err.fmt(f),
             Error::Message(err) => err.fmt(f),
         }
     }
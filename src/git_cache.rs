@@ -0,0 +1,283 @@
+use std::env;
+use std::fs;
+use std::hash::DefaultHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::error::MapLog;
+use crate::error::Result;
+use crate::structs;
+
+/// Files inside git folder which change together with reported git information
+const GIT_DIR_FILES: [&str; 12] = [
+    "HEAD",
+    "index",
+    "ORIG_HEAD",
+    "FETCH_HEAD",
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REVERT_HEAD",
+    "BISECT_LOG",
+    "rebase-merge",
+    "rebase-apply",
+    "sequencer",
+    "info/sparse-checkout",
+];
+
+/// Files inside common git folder, shared between worktrees
+const COMMON_DIR_FILES: [&str; 5] = [
+    "packed-refs",
+    "refs/heads",
+    "refs/remotes",
+    "refs/stash",
+    "shallow",
+];
+
+/// Modification time and size of each file git information depends on.
+/// Working tree isn't scanned, its edits show up once cached result expires
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Fingerprint {
+    files: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl Fingerprint {
+    pub(crate) fn new(repo: &git2::Repository) -> Result<Self> {
+        let git_dir = repo.path();
+        let common_dir = repo.commondir();
+
+        let mut files: Vec<_> = GIT_DIR_FILES.iter().map(|f| git_dir.join(f)).collect();
+        files.extend(COMMON_DIR_FILES.iter().map(|f| common_dir.join(f)));
+
+        // Branch reference is updated in place by commits, its upstream by fetch and push.
+        // Both are rewritten inside subfolders without touching mtime of refs folders
+        if let Some(reference) = repo.find_reference("HEAD")?.symbolic_target() {
+            files.push(common_dir.join(reference));
+
+            if let Some(upstream) = repo
+                .branch_upstream_name(reference)
+                .ok()
+                .and_then(|u| u.as_str().map(String::from))
+            {
+                files.push(common_dir.join(upstream));
+            }
+        }
+
+        Ok(Fingerprint {
+            files: files.iter().map(|f| file_stamp(f)).collect(),
+        })
+    }
+}
+
+#[inline]
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Identity of a cached result: repository, options and state of its files
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct CacheKey {
+    path: path::PathBuf,
+    options: String,
+    fingerprint: Fingerprint,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct CacheEntry<K, T> {
+    key: K,
+    created: SystemTime,
+    value: T,
+}
+
+/// Cached file status and ahead/behind information
+pub(crate) type CachedStatus = (
+    structs::GitFileStatus,
+    Option<structs::GitBranchAheadBehind>,
+);
+
+/// File status and ahead/behind information of a repository stored in cache folder
+pub(crate) struct DiskCache {
+    file: path::PathBuf,
+    key: CacheKey,
+}
+
+impl DiskCache {
+    pub(crate) fn new(repo: &git2::Repository, options: String) -> Result<Self> {
+        let folder = cache_folder().ok_or("Cache folder is unknown")?;
+        Self::in_folder(&folder, repo, options)
+    }
+
+    fn in_folder(folder: &Path, repo: &git2::Repository, options: String) -> Result<Self> {
+        let path = repo.path().to_path_buf();
+
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+
+        Ok(DiskCache {
+            file: folder.join(format!("{:016x}.json", hasher.finish())),
+            key: CacheKey {
+                path,
+                options,
+                fingerprint: Fingerprint::new(repo)?,
+            },
+        })
+    }
+
+    /// Returns cached result if the repository hasn't changed and the result isn't older than `ttl`
+    pub(crate) fn load(&self, ttl: Duration) -> Option<CachedStatus> {
+        // Missing file is an ordinary cache miss
+        let content = fs::read_to_string(&self.file).ok()?;
+        let entry: CacheEntry<CacheKey, CachedStatus> =
+            serde_json::from_str(&content).ok_or_log()?;
+
+        let fresh = entry.created.elapsed().is_ok_and(|age| age < ttl);
+        (fresh && entry.key == self.key).then_some(entry.value)
+    }
+
+    pub(crate) fn store(
+        &self,
+        file_status: &structs::GitFileStatus,
+        branch_ahead_behind: &Option<structs::GitBranchAheadBehind>,
+    ) -> Result<()> {
+        let entry = CacheEntry {
+            key: &self.key,
+            created: SystemTime::now(),
+            value: (file_status, branch_ahead_behind),
+        };
+
+        if let Some(folder) = self.file.parent() {
+            fs::create_dir_all(folder)?;
+        }
+
+        // Concurrent prompts never see a partially written file
+        let temporary = self.file.with_extension(format!("{}.tmp", process::id()));
+        fs::write(&temporary, serde_json::to_string(&entry)?)?;
+        fs::rename(&temporary, &self.file)?;
+        Ok(())
+    }
+}
+
+fn cache_folder() -> Option<path::PathBuf> {
+    env::var_os("XDG_CACHE_HOME")
+        .map(path::PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| path::PathBuf::from(h).join(".cache")))
+        .map(|p| p.join(env!("CARGO_BIN_NAME")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::DiskCache;
+    use crate::structs;
+    use crate::test_util::TestDir;
+    use std::path::Path;
+    use std::time::Duration;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    /// Repository with a committed file and a branch tracking "origin/master"
+    fn fixture(name: &str) -> (TestDir, git2::Repository) {
+        let path = TestDir::new(&format!("cache-{}", name));
+
+        let repo = git2::Repository::init(path.join("repo")).unwrap();
+        repo.set_head("refs/heads/master").unwrap();
+        std::fs::write(path.join("repo/file.txt"), "a\n").unwrap();
+
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let oid = repo
+            .commit(Some("HEAD"), &signature, &signature, "a", &tree, &[])
+            .unwrap();
+        repo.reference("refs/remotes/origin/master", oid, true, "fetch")
+            .unwrap();
+
+        repo.remote("origin", "https://example.com/repo.git")
+            .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("branch.master.remote", "origin").unwrap();
+        config
+            .set_str("branch.master.merge", "refs/heads/master")
+            .unwrap();
+
+        drop(tree);
+        (path, repo)
+    }
+
+    fn store(path: &Path, repo: &git2::Repository) {
+        DiskCache::in_folder(&path.join("cache"), repo, "options".to_string())
+            .unwrap()
+            .store(&structs::GitFileStatus::default(), &None)
+            .unwrap();
+    }
+
+    fn load(path: &Path, repo: &git2::Repository, ttl: Duration) -> bool {
+        DiskCache::in_folder(&path.join("cache"), repo, "options".to_string())
+            .unwrap()
+            .load(ttl)
+            .is_some()
+    }
+
+    #[test]
+    fn unchanged_repository_hit_test() {
+        let (path, repo) = fixture("hit");
+        store(&path, &repo);
+
+        assert!(load(&path, &repo, TTL));
+    }
+
+    #[test]
+    fn expired_entry_miss_test() {
+        let (path, repo) = fixture("expired");
+        store(&path, &repo);
+
+        assert!(!load(&path, &repo, Duration::ZERO));
+    }
+
+    #[test]
+    fn other_options_miss_test() {
+        let (path, repo) = fixture("options");
+        store(&path, &repo);
+
+        let cache = DiskCache::in_folder(&path.join("cache"), &repo, "other".to_string()).unwrap();
+        assert!(cache.load(TTL).is_none());
+    }
+
+    #[test]
+    fn staged_edit_miss_test() {
+        let (path, repo) = fixture("staged");
+        store(&path, &repo);
+
+        std::fs::write(path.join("repo/file.txt"), "a\nb\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("file.txt")).unwrap();
+        index.write().unwrap();
+
+        assert!(!load(&path, &repo, TTL));
+    }
+
+    #[test]
+    fn upstream_update_miss_test() {
+        let (path, repo) = fixture("upstream");
+        store(&path, &repo);
+
+        // Fetch rewrites the loose reference file in place, same size, new content
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        let tree = head.tree().unwrap();
+        let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+        let oid = repo
+            .commit(None, &signature, &signature, "b", &tree, &[&head])
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        repo.reference("refs/remotes/origin/master", oid, true, "fetch")
+            .unwrap();
+
+        assert!(!load(&path, &repo, TTL));
+    }
+}
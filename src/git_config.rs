@@ -0,0 +1,195 @@
+use crate::args;
+use crate::error::MapLog;
+use crate::error::Result;
+use crate::structs;
+
+/// Option value stored in git config. Missing key is None value, invalid value is an error
+pub(crate) trait ConfigValue: Sized {
+    fn get(config: &git2::Config, key: &str) -> Result<Option<Self>>;
+}
+
+impl ConfigValue for bool {
+    fn get(config: &git2::Config, key: &str) -> Result<Option<Self>> {
+        found(key, config.get_bool(key))
+    }
+}
+
+impl ConfigValue for String {
+    fn get(config: &git2::Config, key: &str) -> Result<Option<Self>> {
+        found(key, config.get_string(key))
+    }
+}
+
+impl ConfigValue for u64 {
+    fn get(config: &git2::Config, key: &str) -> Result<Option<Self>> {
+        found(key, config.get_i64(key))?
+            .map(|v| u64::try_from(v).map_err(|_| invalid(key, &v.to_string())))
+            .transpose()
+    }
+}
+
+impl ConfigValue for usize {
+    fn get(config: &git2::Config, key: &str) -> Result<Option<Self>> {
+        found(key, config.get_i64(key))?
+            .map(|v| usize::try_from(v).map_err(|_| invalid(key, &v.to_string())))
+            .transpose()
+    }
+}
+
+/// Enums are written the same way as their command line values
+macro_rules! config_value_enum {
+    ($($enum:ty),+) => {
+        $(
+            impl ConfigValue for $enum {
+                fn get(config: &git2::Config, key: &str) -> Result<Option<Self>> {
+                    found(key, config.get_string(key))?
+                        .map(|v| {
+                            <$enum as clap::ValueEnum>::from_str(&v, true)
+                                .map_err(|_| invalid(key, &v))
+                        })
+                        .transpose()
+                }
+            }
+        )+
+    };
+}
+
+config_value_enum!(
+    args::ThemeNames,
+    args::ThemeSymbolsNames,
+    structs::ReferenceDisplay,
+    structs::UntrackedFiles
+);
+
+#[inline]
+fn found<T>(key: &str, value: Result<T, git2::Error>) -> Result<Option<T>> {
+    match value {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(err) => Err(format!("{}: {}", key, err.message()).into()),
+    }
+}
+
+#[inline]
+pub(crate) fn invalid(key: &str, value: &str) -> crate::error::Error {
+    format!("invalid value '{}' of {}", value, key).into()
+}
+
+/// Key of an option in the section named after the binary, e.g. "ilsore-format.theme-name"
+#[inline]
+pub(crate) fn key(name: &str) -> String {
+    format!("{}.{}", env!("CARGO_BIN_NAME"), name)
+}
+
+/// Value of option `name` or `default_value` if it isn't set. Invalid values are reported
+#[inline]
+pub(crate) fn config_var<T: ConfigValue>(config: &git2::Config, name: &str, default_value: T) -> T {
+    config_option_var(config, name, None).unwrap_or(default_value)
+}
+
+/// Value of option `name` or `default_value` if it isn't set. Invalid values are reported
+#[inline]
+pub(crate) fn config_option_var<T: ConfigValue>(
+    config: &git2::Config,
+    name: &str,
+    default_value: Option<T>,
+) -> Option<T> {
+    T::get(config, &key(name))
+        .ok_or_log()
+        .flatten()
+        .or(default_value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{config_option_var, config_var};
+    use crate::args;
+    use crate::structs;
+    use crate::test_util::TestDir;
+
+    fn config(content: &str) -> git2::Config {
+        let folder = TestDir::new("config");
+        let file = folder.join("config");
+        std::fs::write(&file, content).unwrap();
+        git2::Config::open(&file).unwrap().snapshot().unwrap()
+    }
+
+    #[test]
+    fn typed_values_test() {
+        let config = config(
+            "[ilsore-format]\n\
+             disable-git = yes\n\
+             count-limit = 9\n\
+             timeout-ms = 250\n\
+             reference = refs/heads/main\n\
+             theme-name = ilsore-no-color\n\
+             theme-symbols = ASCII\n\
+             reference-display = last\n\
+             untracked-files = all\n",
+        );
+
+        assert!(config_var(&config, "disable-git", false));
+        assert_eq!(config_var(&config, "count-limit", 99usize), 9);
+        assert_eq!(
+            config_option_var::<u64>(&config, "timeout-ms", None),
+            Some(250)
+        );
+        assert_eq!(
+            config_var(&config, "reference", "HEAD".to_string()),
+            "refs/heads/main"
+        );
+        assert!(matches!(
+            config_var(&config, "theme-name", args::ThemeNames::IlsoreColor),
+            args::ThemeNames::IlsoreNoColor
+        ));
+        assert!(matches!(
+            config_var(&config, "theme-symbols", args::ThemeSymbolsNames::Utf8Power),
+            args::ThemeSymbolsNames::Ascii
+        ));
+        assert!(matches!(
+            config_var(
+                &config,
+                "reference-display",
+                structs::ReferenceDisplay::Short
+            ),
+            structs::ReferenceDisplay::Last
+        ));
+        assert_eq!(
+            config_var(&config, "untracked-files", structs::UntrackedFiles::Normal),
+            structs::UntrackedFiles::All
+        );
+    }
+
+    #[test]
+    fn invalid_values_fall_back_test() {
+        let config = config(
+            "[ilsore-format]\n\
+             disable-git = maybe\n\
+             count-limit = -1\n\
+             timeout-ms = soon\n\
+             theme-name = rainbow\n",
+        );
+
+        assert!(!config_var(&config, "disable-git", false));
+        assert_eq!(config_var(&config, "count-limit", 99usize), 99);
+        assert_eq!(
+            config_option_var::<u64>(&config, "timeout-ms", Some(5)),
+            Some(5)
+        );
+        assert!(matches!(
+            config_var(&config, "theme-name", args::ThemeNames::IlsoreColor),
+            args::ThemeNames::IlsoreColor
+        ));
+    }
+
+    #[test]
+    fn missing_values_use_default_test() {
+        let config = config("[core]\n\tbare = false\n");
+
+        assert!(config_var(&config, "disable-git", true));
+        assert_eq!(
+            config_option_var::<String>(&config, "describe-pattern", None),
+            None
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::error::Result;
+use crate::structs;
+
+/// Hosting of the remote HEAD branch tracks, "origin" if it tracks nothing.
+/// None value if there is no such remote
+pub(crate) fn remote(
+    repo: &git2::Repository,
+    reference_name: Option<&str>,
+    self_hosted_pattern: Option<&str>,
+) -> Result<Option<structs::GitRemote>> {
+    let upstream_remote = reference_name
+        .filter(|r| r.starts_with("refs/heads/"))
+        .and_then(|r| repo.branch_upstream_remote(r).ok())
+        .and_then(|r| r.as_str().map(String::from));
+    let remote_name = upstream_remote.as_deref().unwrap_or("origin");
+
+    // "." tracks a branch of the same repository
+    if remote_name == "." {
+        return Ok(None);
+    }
+
+    let remote = match repo.find_remote(remote_name) {
+        Ok(remote) => remote,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let url = remote
+        .url()
+        .ok_or("remote URL can't be converted to an UTF-8 string")?;
+
+    Ok(Some(classify(url, self_hosted_pattern)))
+}
+
+fn classify(url: &str, self_hosted_pattern: Option<&str>) -> structs::GitRemote {
+    let Some((host, path)) = split_url(url) else {
+        return structs::GitRemote {
+            provider: structs::GitProvider::Local,
+            slug: None,
+        };
+    };
+
+    let host = host.to_ascii_lowercase();
+    let provider = match host.as_str() {
+        "github.com" => structs::GitProvider::GitHub,
+        "gitlab.com" => structs::GitProvider::GitLab,
+        "bitbucket.org" => structs::GitProvider::Bitbucket,
+        "codeberg.org" | "gitea.com" => structs::GitProvider::Gitea,
+        "dev.azure.com" | "ssh.dev.azure.com" => structs::GitProvider::AzureDevOps,
+        h if h.ends_with(".visualstudio.com") => structs::GitProvider::AzureDevOps,
+        h if self_hosted_pattern.is_some_and(|p| wildcard_match(p, h)) => {
+            structs::GitProvider::SelfHosted
+        }
+        _ => structs::GitProvider::Unknown,
+    };
+
+    let slug = match provider {
+        // "org/project/_git/repo" over https and "v3/org/project/repo" over ssh
+        structs::GitProvider::AzureDevOps => path
+            .split('/')
+            .filter(|p| !p.is_empty() && *p != "_git" && *p != "v3")
+            .collect::<Vec<_>>()
+            .join("/"),
+        _ => path.trim_matches('/').to_string(),
+    };
+    let slug = slug.strip_suffix(".git").unwrap_or(&slug).to_string();
+
+    structs::GitRemote {
+        provider,
+        slug: (!slug.is_empty()).then_some(slug),
+    }
+}
+
+/// Splits remote URL into host and path. None value for local repositories
+fn split_url(url: &str) -> Option<(&str, &str)> {
+    if let Some((scheme, rest)) = url.split_once("://") {
+        if scheme == "file" {
+            return None;
+        }
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+        let host = host.split_once(':').map_or(host, |(h, _)| h);
+        return Some((host, path));
+    }
+
+    // scp-like syntax "user@host:path", colon before any slash
+    let colon = url.find(':')?;
+    if url[..colon].contains('/') {
+        return None;
+    }
+    let authority = &url[..colon];
+    let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+    // Windows drive letter, e.g. "C:\repo"
+    if host.len() < 2 {
+        return None;
+    }
+    Some((host, &url[colon + 1..]))
+}
+
+/// Glob-like match where `*` matches any sequence of characters
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, rest)) => {
+            let Some(value) = value.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=value.len())
+                .filter(|&i| value.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &value[i..]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{classify, split_url, wildcard_match};
+    use crate::structs::GitProvider;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("https://github.com/owner/repo.git", Some(("github.com", "owner/repo.git")))]
+    #[case("ssh://git@example.com:2222/owner/repo", Some(("example.com", "owner/repo")))]
+    #[case("git@gitlab.com:group/sub/repo.git", Some(("gitlab.com", "group/sub/repo.git")))]
+    #[case("file:///srv/repo.git", None)]
+    #[case("/srv/repo.git", None)]
+    #[case("../repo", None)]
+    #[case("./dir:with/colon", None)]
+    #[case("C:\\repo", None)]
+    fn split_url_test(#[case] url: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(split_url(url), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "https://github.com/owner/repo.git",
+        GitProvider::GitHub,
+        Some("owner/repo")
+    )]
+    #[case(
+        "git@gitlab.com:group/sub/repo.git",
+        GitProvider::GitLab,
+        Some("group/sub/repo")
+    )]
+    #[case(
+        "git@bitbucket.org:owner/repo.git",
+        GitProvider::Bitbucket,
+        Some("owner/repo")
+    )]
+    #[case(
+        "https://codeberg.org/owner/repo",
+        GitProvider::Gitea,
+        Some("owner/repo")
+    )]
+    #[case(
+        "https://dev.azure.com/org/project/_git/repo",
+        GitProvider::AzureDevOps,
+        Some("org/project/repo")
+    )]
+    #[case(
+        "git@ssh.dev.azure.com:v3/org/project/repo",
+        GitProvider::AzureDevOps,
+        Some("org/project/repo")
+    )]
+    #[case(
+        "git@git.corp.example:team/repo.git",
+        GitProvider::SelfHosted,
+        Some("team/repo")
+    )]
+    #[case(
+        "https://example.org/team/repo.git",
+        GitProvider::Unknown,
+        Some("team/repo")
+    )]
+    #[case("/srv/repo.git", GitProvider::Local, None)]
+    fn classify_test(#[case] url: &str, #[case] provider: GitProvider, #[case] slug: Option<&str>) {
+        let remote = classify(url, Some("git.*.example"));
+        assert_eq!(remote.provider, provider);
+        assert_eq!(remote.slug.as_deref(), slug);
+    }
+
+    #[rstest]
+    #[case("git.corp.example", "git.corp.example", true)]
+    #[case("*.example", "git.corp.example", true)]
+    #[case("git.*", "git.corp.example", true)]
+    #[case("*corp*", "git.corp.example", true)]
+    #[case("*.example", "example", false)]
+    #[case("git.*.com", "git.corp.example", false)]
+    fn wildcard_match_test(#[case] pattern: &str, #[case] value: &str, #[case] expected: bool) {
+        assert_eq!(wildcard_match(pattern, value), expected);
+    }
+}
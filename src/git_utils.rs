@@ -1,29 +1,57 @@
 use std::borrow::Cow;
 use std::env;
-use std::path;
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::io::Read;
 use std::path::Path;
+use std::sync::mpsc;
 use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
-use crate::error;
+use crate::daemon;
+use crate::error::LogError;
 use crate::error::MapLog;
 use crate::error::Result;
+use crate::git_cache;
+use crate::git_config;
+use crate::git_config::config_option_var;
+use crate::git_config::config_var;
+use crate::git_remote;
 use crate::structs;
-use crate::util::LastPart;
 
-pub(crate) fn process_current_dir(
+pub(crate) fn process_location(
+    location: &structs::GitRepoLocation,
     options: &structs::GetGitInfoOptions,
 ) -> Result<structs::GitOutputOptions> {
-    let git_dir_buf =
-        git_subfolder(options)?.ok_or_else(|| error::Error::from("Not found .git folder"))?;
+    let started = Instant::now();
 
-    process_repo(&git_dir_buf, options)
+    let daemon_socket = options.daemon_socket.as_deref().filter(|s| s.exists());
+    if let Some(result) =
+        daemon_socket.and_then(|s| daemon::query(s, location, options).ok_or_log())
+    {
+        return Ok(result);
+    }
+
+    // Time spent waiting for daemon is taken from the budget
+    let timeout_ms = options.timeout_ms.map(|t| {
+        t.saturating_sub(u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX))
+    });
+    process_repo(
+        location,
+        &structs::GetGitInfoOptions {
+            timeout_ms,
+            ..options.clone()
+        },
+    )
 }
 
-fn git_subfolder(options: &structs::GetGitInfoOptions) -> Result<Option<path::PathBuf>> {
-    let path = options
-        .start_folder
-        .as_ref()
-        .map(Path::new)
+/// Finds repository the same way git does, respecting `GIT_DIR`, `GIT_WORK_TREE`,
+/// `GIT_CEILING_DIRECTORIES` and `GIT_DISCOVERY_ACROSS_FILESYSTEM`.
+/// None value of `start_folder` means current folder
+pub(crate) fn discover_repo(start_folder: Option<&Path>) -> Result<structs::GitRepoLocation> {
+    let path = start_folder
         .map(Cow::from)
         .map(Ok)
         .unwrap_or_else(|| env::current_dir().map(Cow::from))?;
@@ -32,90 +60,486 @@ fn git_subfolder(options: &structs::GetGitInfoOptions) -> Result<Option<path::Pa
         return Err(format!("Path '{}' doesn't exist", path.display()).into());
     }
 
-    for sub_path in path.ancestors() {
-        let folder = sub_path.join(".git");
-        if folder.exists() {
-            return Ok(Some(sub_path.to_path_buf()));
+    // libgit2 reads GIT_DIR and GIT_CEILING_DIRECTORIES only when search path isn't given
+    let repo = match env::var_os("GIT_DIR") {
+        Some(_) => git2::Repository::open_from_env()?,
+        None => {
+            let ceiling_dirs = env::var_os("GIT_CEILING_DIRECTORIES")
+                .map(|v| env::split_paths(&v).collect::<Vec<_>>())
+                .unwrap_or_default();
+            git2::Repository::open_ext(
+                path.as_ref(),
+                git2::RepositoryOpenFlags::FROM_ENV,
+                ceiling_dirs,
+            )?
         }
+    };
+
+    Ok(structs::GitRepoLocation {
+        git_dir: repo.path().to_path_buf(),
+        work_dir: repo.workdir().map(Path::to_path_buf),
+    })
+}
+
+/// Configuration of discovered repository including global and system ones
+pub(crate) fn repo_config(location: &structs::GitRepoLocation) -> Result<git2::Config> {
+    Ok(open_repo(location)?.config()?.snapshot()?)
+}
+
+/// Opens discovered repository without searching again
+fn open_repo(location: &structs::GitRepoLocation) -> Result<git2::Repository> {
+    let repo = git2::Repository::open_ext(
+        &location.git_dir,
+        git2::RepositoryOpenFlags::NO_SEARCH,
+        &[] as &[&OsStr],
+    )?;
+
+    if let Some(work_dir) = &location.work_dir {
+        if repo.workdir() != Some(work_dir.as_path()) {
+            repo.set_workdir(work_dir, false)?;
+        }
+    }
+
+    Ok(repo)
+}
+
+fn repo_kind(repo: &git2::Repository) -> structs::GitRepoKind {
+    if repo.is_bare() {
+        structs::GitRepoKind::Bare
+    } else if repo.is_worktree() {
+        structs::GitRepoKind::LinkedWorktree
+    } else if is_submodule_git_dir(repo.path()) {
+        structs::GitRepoKind::Submodule
+    } else {
+        structs::GitRepoKind::Main
+    }
+}
+
+/// Linked worktree information, main worktree is told apart by `repo_kind`
+fn worktree(repo: &git2::Repository) -> Result<Option<structs::GitWorktree>> {
+    if !repo.is_worktree() {
+        return Ok(None);
     }
-    Ok(None)
+
+    let worktree = git2::Worktree::open_from_repository(repo)?;
+    Ok(Some(structs::GitWorktree {
+        name: worktree.name().map(String::from),
+    }))
+}
+
+/// Partial clone fetches missing objects from promisor remote on demand
+fn is_partial_clone(repo: &git2::Repository) -> Result<bool> {
+    let config = repo.config()?.snapshot()?;
+    if config.get_str("extensions.partialclone").is_ok() {
+        return Ok(true);
+    }
+
+    let mut promisor = false;
+    config
+        .entries(Some(r"remote\..*\.promisor"))?
+        .for_each(|entry| promisor |= entry.value() == Some("true"))?;
+    Ok(promisor)
+}
+
+fn sparse_checkout(repo: &git2::Repository) -> Result<Option<structs::GitSparseCheckout>> {
+    let config = repo.config()?.snapshot()?;
+    if !config.get_bool("core.sparseCheckout").unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let cone = config.get_bool("core.sparseCheckoutCone").unwrap_or(false);
+    // Patterns are per worktree
+    let patterns = match fs::read_to_string(repo.path().join("info/sparse-checkout")) {
+        Ok(content) => sparse_pattern_count(&content, cone),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(Some(structs::GitSparseCheckout { cone, patterns }))
+}
+
+/// Number of patterns as `git sparse-checkout list` shows them
+fn sparse_pattern_count(content: &str, cone: bool) -> usize {
+    let patterns = content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'));
+
+    if !cone {
+        return patterns.count();
+    }
+
+    // Cone mode includes parents of each directory: "/a/" followed by "!/a/*/" is a parent only
+    let patterns = patterns.collect::<Vec<_>>();
+    patterns
+        .iter()
+        .filter(|p| !p.starts_with('!') && **p != "/*")
+        .filter(|p| !patterns.contains(&format!("!{}*/", p).as_str()))
+        .count()
 }
 
-fn process_repo(
-    path: &Path,
+/// Submodule repositories are absorbed into `.git/modules` of their superproject
+fn is_submodule_git_dir(git_dir: &Path) -> bool {
+    git_dir.ancestors().skip(1).any(|p| {
+        p.file_name() == Some(OsStr::new("modules"))
+            && p.parent().and_then(Path::file_name) == Some(OsStr::new(".git"))
+    })
+}
+
+pub(crate) fn process_repo(
+    location: &structs::GitRepoLocation,
     input_options: &structs::GetGitInfoOptions,
 ) -> Result<structs::GitOutputOptions> {
-    let options = configuration_overrided(path, input_options)?;
+    let repo = open_repo(location)?;
+    let options = configuration_overrided(&repo, input_options)?;
+    let deadline = options.timeout.map(|t| Instant::now() + t);
+    let expired = || deadline.is_some_and(|d| Instant::now() >= d);
+
+    // Changes inside submodules don't touch any file fingerprint looks at
+    let cache = match options.use_cache && !options.include_submodules && !expired() {
+        true => {
+            git_cache::DiskCache::new(&repo, cache_options_key(input_options, &options)).ok_or_log()
+        }
+        false => None,
+    };
+    let cached = cache
+        .as_ref()
+        .filter(|_| !expired())
+        .and_then(|c| c.load(Duration::from_millis(input_options.cache_ttl_ms)));
 
-    let mut head_info_result: Option<structs::GitHeadInfo> = None;
-    let mut branch_ahead_behind_result: Option<structs::GitBranchAheadBehind> = None;
-    let mut file_status_result: Option<structs::GitFileStatus> = None;
+    let mut result = structs::GitOutputOptions {
+        head_info: None,
+        file_status: None,
+        branch_ahead_behind: None,
+        push_ahead_behind: None,
+        base_ahead_behind: None,
+        remote: None,
+        repo_state: None,
+        stash_count: None,
+        submodules: None,
+        diff_stats: None,
+        lfs: None,
+        timed_out: false,
+        upstream_checked: false,
+        repo_kind: repo_kind(&repo),
+        shallow: false,
+        partial_clone: false,
+        sparse_checkout: None,
+        worktree: None,
+    };
 
-    thread::scope(|s| {
-        s.spawn(|| {
-            let repo_option = git2::Repository::open(path).ok_or_log();
-            if repo_option.is_none() {
-                return;
-            };
-            let repo = repo_option.unwrap();
-            let head_info_internal = head_info(&repo, input_options.reference_name).ok_or_log();
+    let (sender, receiver) = mpsc::channel();
+
+    let reference_name = input_options.reference_name.to_string();
+    let ahead_behind_requested = options.include_ahead_behind;
+    let cached_ahead_behind = cached.is_some();
+    let base_reference = options.base_reference.clone();
+    let describe_pattern = options.describe_pattern.clone();
+    let abbrev = options.abbrev;
+    let self_hosted_pattern = options.self_hosted_pattern.clone();
 
-            let ahead_behind = match options.include_ahead_behind {
-                true => graph_ahead_behind(&repo, &head_info_internal).ok_or_log(),
-                false => Some(structs::GitBranchAheadBehind {
+    // Branch name arrives first, so it's shown even if comparisons below run out of time
+    let head_reference_name = reference_name.clone();
+    spawn_worker(&sender, location, move |repo| {
+        let head_info_internal = head_info(&repo, &head_reference_name).ok_or_log();
+        let oid_short = head_info_internal
+            .as_ref()
+            .and_then(|h| h.oid)
+            .and_then(|oid| short_id(&repo, oid, abbrev).ok_or_log());
+
+        WorkerResult::HeadInfo(head_info_internal.map(|h| structs::GitHeadInfo {
+            oid_short,
+            ..h.into()
+        }))
+    });
+
+    spawn_worker(&sender, location, move |repo| {
+        let head_info_internal = head_info(&repo, &reference_name).ok_or_log();
+        // Branch without commits has nothing to compare
+        let unborn = head_info_internal.as_ref().is_some_and(|h| h.unborn);
+
+        // Commit graph of shallow clone is cut, counts would be wrong and slow to compute
+        let shallow = repo.is_shallow();
+        let include_ahead_behind = ahead_behind_requested && !shallow && !cached_ahead_behind;
+        let include_upstream = ahead_behind_requested && shallow && !cached_ahead_behind;
+        let include_push_ahead_behind = ahead_behind_requested && !shallow;
+        let base_reference = base_reference.filter(|_| !shallow);
+
+        let branch_ahead_behind = match include_ahead_behind {
+            true if unborn => None,
+            true => graph_ahead_behind(&repo, &head_info_internal).ok_or_log(),
+            // Only presence of tracking branch is known, counts are left out as if excluded
+            false if include_upstream && !unborn => tracking_oids(&repo, &head_info_internal)
+                .ok_or_log()
+                .map(|_| structs::GitBranchAheadBehind {
                     ahead: 0,
                     behind: 0,
                 }),
-            };
+            false if include_upstream => None,
+            false => Some(structs::GitBranchAheadBehind {
+                ahead: 0,
+                behind: 0,
+            }),
+        };
+
+        let push_ahead_behind = match include_push_ahead_behind && !unborn {
+            true => push_ahead_behind(&repo, &head_info_internal)
+                .ok_or_log()
+                .flatten(),
+            false => None,
+        };
 
-            branch_ahead_behind_result = ahead_behind;
-            head_info_result = head_info_internal.map(|h| h.into());
+        let base_ahead_behind = base_reference.filter(|_| !unborn).and_then(|base| {
+            base_ahead_behind(&repo, &head_info_internal, &base)
+                .ok_or_log()
+                .flatten()
         });
 
-        s.spawn(|| {
-            let repo_option = git2::Repository::open(path).ok_or_log();
-            if repo_option.is_none() {
-                return;
-            };
-            let repo = repo_option.unwrap();
-            file_status_result = file_status(&repo, &options).ok_or_log();
+        let describe = match head_info_internal.as_ref().is_some_and(|h| h.detached) {
+            true => describe(&repo, describe_pattern.as_deref())
+                .ok_or_log()
+                .flatten(),
+            false => None,
+        };
+
+        let remote = git_remote::remote(
+            &repo,
+            head_info_internal
+                .as_ref()
+                .and_then(|h| h.reference_name.as_deref()),
+            self_hosted_pattern.as_deref(),
+        )
+        .ok_or_log()
+        .flatten();
+
+        let oid = head_info_internal.as_ref().and_then(|h| h.oid);
+        // Reference may point to an annotated tag
+        let commit = oid.and_then(|oid| {
+            repo.find_object(oid, None)
+                .and_then(|o| o.peel_to_commit())
+                .ok_or_log()
         });
+
+        WorkerResult::Head {
+            // Abbreviated hash is left to `HeadInfo` worker, uniqueness check is slow
+            head_info: head_info_internal.map(|h| structs::GitHeadInfo {
+                describe,
+                commit_time: commit.as_ref().map(|c| c.time().seconds()),
+                commit_author: commit
+                    .as_ref()
+                    .and_then(|c| c.author().name().map(String::from)),
+                ..h.into()
+            }),
+            branch_ahead_behind,
+            push_ahead_behind,
+            base_ahead_behind,
+            remote,
+            repo_state: repo_state(&repo).ok_or_log().flatten(),
+            shallow,
+        }
     });
 
-    Ok(structs::GitOutputOptions {
-        head_info: head_info_result,
-        file_status: file_status_result,
-        branch_ahead_behind: branch_ahead_behind_result,
-    })
+    spawn_worker(&sender, location, |repo| WorkerResult::Checkout {
+        partial_clone: is_partial_clone(&repo).ok_or_log().unwrap_or_default(),
+        sparse_checkout: sparse_checkout(&repo).ok_or_log().flatten(),
+        worktree: worktree(&repo).ok_or_log().flatten(),
+    });
+
+    if cached.is_none() {
+        let status_options = options.clone();
+        spawn_worker(&sender, location, move |repo| {
+            WorkerResult::FileStatus(file_status(&repo, &status_options).ok_or_log())
+        });
+    }
+
+    spawn_worker(&sender, location, |mut repo| {
+        WorkerResult::StashCount(stash_count(&mut repo).ok_or_log())
+    });
+
+    spawn_worker(&sender, location, |repo| match uses_lfs(&repo) {
+        true => WorkerResult::Lfs(lfs_status(&repo).ok_or_log()),
+        false => WorkerResult::Lfs(None),
+    });
+
+    if options.include_diff_stats {
+        let include_submodules = options.include_submodules;
+        spawn_worker(&sender, location, move |repo| {
+            WorkerResult::DiffStats(diff_stats(&repo, include_submodules).ok_or_log())
+        });
+    }
+
+    if options.include_submodules {
+        spawn_worker(&sender, location, |repo| {
+            WorkerResult::Submodules(submodule_summary(&repo).ok_or_log().flatten())
+        });
+    }
+
+    // Workers which failed to open the repository drop their senders without a result
+    drop(sender);
+
+    loop {
+        let received = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(mpsc::RecvTimeoutError::from),
+        };
+
+        match received {
+            // Complete head information may have arrived already, only its hash is missing
+            Ok(WorkerResult::HeadInfo(head_info)) => {
+                result.head_info = match (result.head_info.take(), head_info) {
+                    (Some(complete), Some(h)) => Some(structs::GitHeadInfo {
+                        oid_short: h.oid_short,
+                        ..complete
+                    }),
+                    (complete, h) => complete.or(h),
+                };
+            }
+            Ok(WorkerResult::Head {
+                head_info,
+                branch_ahead_behind,
+                push_ahead_behind,
+                base_ahead_behind,
+                remote,
+                repo_state,
+                shallow,
+            }) => {
+                result.head_info = match (result.head_info.take(), head_info) {
+                    (Some(partial), Some(h)) => Some(structs::GitHeadInfo {
+                        oid_short: partial.oid_short,
+                        ..h
+                    }),
+                    (partial, h) => h.or(partial),
+                };
+                result.branch_ahead_behind = branch_ahead_behind;
+                result.upstream_checked = true;
+                result.push_ahead_behind = push_ahead_behind;
+                result.base_ahead_behind = base_ahead_behind;
+                result.remote = remote;
+                result.repo_state = repo_state;
+                result.shallow = shallow;
+            }
+            Ok(WorkerResult::Checkout {
+                partial_clone,
+                sparse_checkout,
+                worktree,
+            }) => {
+                result.partial_clone = partial_clone;
+                result.sparse_checkout = sparse_checkout;
+                result.worktree = worktree;
+            }
+            Ok(WorkerResult::FileStatus(file_status)) => result.file_status = file_status,
+            Ok(WorkerResult::StashCount(stash_count)) => result.stash_count = stash_count,
+            Ok(WorkerResult::Submodules(submodules)) => result.submodules = submodules,
+            Ok(WorkerResult::DiffStats(diff_stats)) => result.diff_stats = diff_stats,
+            Ok(WorkerResult::Lfs(lfs)) => result.lfs = lfs,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Remaining workers are abandoned and finish on their own
+                result.timed_out = true;
+                break;
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    match (cached, &cache, &result.file_status) {
+        (Some((file_status, branch_ahead_behind)), _, _) => {
+            result.file_status = Some(file_status);
+            result.branch_ahead_behind = branch_ahead_behind;
+            result.upstream_checked = true;
+        }
+        (None, Some(cache), Some(file_status)) if !result.timed_out => {
+            cache
+                .store(file_status, &result.branch_ahead_behind)
+                .ok_or_log();
+        }
+        _ => (),
+    }
+
+    Ok(result)
+}
+
+/// Result of a single worker thread spawned by `process_repo`
+enum WorkerResult {
+    /// Reference name and abbreviated hash, before expensive work of `Head`
+    HeadInfo(Option<structs::GitHeadInfo>),
+    Head {
+        head_info: Option<structs::GitHeadInfo>,
+        branch_ahead_behind: Option<structs::GitBranchAheadBehind>,
+        push_ahead_behind: Option<structs::GitBranchAheadBehind>,
+        base_ahead_behind: Option<structs::GitBaseAheadBehind>,
+        remote: Option<structs::GitRemote>,
+        repo_state: Option<structs::GitRepoState>,
+        shallow: bool,
+    },
+    /// Layout of checkout, cheap but still reading config and files
+    Checkout {
+        partial_clone: bool,
+        sparse_checkout: Option<structs::GitSparseCheckout>,
+        worktree: Option<structs::GitWorktree>,
+    },
+    FileStatus(Option<structs::GitFileStatus>),
+    StashCount(Option<usize>),
+    Submodules(Option<structs::GitSubmoduleSummary>),
+    DiffStats(Option<structs::GitDiffStats>),
+    Lfs(Option<structs::GitLfsStatus>),
+}
+
+fn spawn_worker<F>(
+    sender: &mpsc::Sender<WorkerResult>,
+    location: &structs::GitRepoLocation,
+    work: F,
+) where
+    F: FnOnce(git2::Repository) -> WorkerResult + Send + 'static,
+{
+    let sender = sender.clone();
+    let location = location.clone();
+
+    thread::spawn(move || {
+        if let Some(repo) = open_repo(&location).ok_or_log() {
+            // Receiver is gone if the time budget ran out
+            let _ = sender.send(work(repo));
+        }
+    });
 }
 
 #[derive(Debug)]
 struct GitHeadInfoInternal {
     pub reference_name: Option<String>,
+    pub reference_short: Option<String>,
     pub oid: Option<git2::Oid>,
     pub detached: bool,
+    pub unborn: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct GetGitInfoOptionsInternal {
     pub include_submodules: bool,
-    pub include_untracked: bool,
+    pub untracked_files: structs::UntrackedFiles,
     pub refresh_status: bool,
     pub include_ahead_behind: bool,
     pub include_workdir_stats: bool,
+    pub include_diff_stats: bool,
+    pub timeout: Option<Duration>,
+    pub use_cache: bool,
+    pub base_reference: Option<String>,
+    pub describe_pattern: Option<String>,
+    pub abbrev: Option<u64>,
+    pub self_hosted_pattern: Option<String>,
 }
 
 impl From<GitHeadInfoInternal> for structs::GitHeadInfo {
     fn from(val: GitHeadInfoInternal) -> Self {
-        let reference_short = val
-            .reference_name
-            .map(|v| v.as_str().last_part().to_string());
-        let oid_short = val.oid.map(|v| v.to_string()[0..8].to_string());
-
         structs::GitHeadInfo {
-            reference_short,
-            oid_short,
+            reference_name: val.reference_name,
+            reference_short: val.reference_short,
+            oid_short: None,
             detached: val.detached,
+            unborn: val.unborn,
+            describe: None,
+            commit_time: None,
+            commit_author: None,
         }
     }
 }
@@ -127,35 +551,242 @@ fn head_info(repo: &git2::Repository, input_reference_name: &str) -> Result<GitH
     let head_info = match reference.kind() {
         None => GitHeadInfoInternal {
             reference_name: None,
+            reference_short: None,
             oid: None,
             detached,
+            unborn: false,
         },
         Some(git2::ReferenceType::Symbolic) => {
             let reference_name = reference.symbolic_target().map(String::from);
 
-            let reference_resolved = reference.resolve().ok_or_log();
-            let oid = reference_resolved.and_then(|r| r.target());
+            // Target of fresh repository or orphan branch doesn't exist until first commit
+            let (reference_resolved, unborn) = match reference.resolve() {
+                Ok(reference_resolved) => (Some(reference_resolved), false),
+                Err(err) if err.code() == git2::ErrorCode::NotFound => (None, true),
+                Err(err) => {
+                    err.log();
+                    (None, false)
+                }
+            };
+            let oid = reference_resolved.as_ref().and_then(|r| r.target());
+            let reference_short = match &reference_resolved {
+                Some(r) => r.shorthand().map(String::from),
+                // Missing reference has no shorthand, unborn HEAD can point to a branch only
+                None => reference_name
+                    .as_deref()
+                    .map(|r| r.strip_prefix("refs/heads/").unwrap_or(r).to_string()),
+            };
 
             GitHeadInfoInternal {
                 reference_name,
+                reference_short,
                 oid,
                 detached,
+                unborn,
             }
         }
         Some(git2::ReferenceType::Direct) => {
             let reference_name = reference.name().map(String::from);
+            let reference_short = reference.shorthand().map(String::from);
             let oid = reference.target();
 
             GitHeadInfoInternal {
                 reference_name,
+                reference_short,
                 oid,
                 detached,
+                unborn: false,
             }
         }
     };
     Ok(head_info)
 }
 
+/// Shortest hash prefix unique in the repository, at least `abbrev` or `core.abbrev` long
+fn short_id(repo: &git2::Repository, oid: git2::Oid, abbrev: Option<u64>) -> Result<String> {
+    let Some(abbrev) = abbrev else {
+        let short_id = repo.find_object(oid, None)?.short_id()?;
+        return Ok(short_id
+            .as_str()
+            .ok_or("short hash can't be converted to an UTF-8 string")?
+            .to_string());
+    };
+
+    let hex = oid.to_string();
+    let min_length = usize::try_from(abbrev)
+        .unwrap_or(hex.len())
+        .clamp(4, hex.len());
+
+    for length in min_length..hex.len() {
+        match repo.find_object_by_prefix(&hex[..length], None) {
+            Ok(_) => return Ok(hex[..length].to_string()),
+            Err(err) if err.code() == git2::ErrorCode::Ambiguous => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(hex)
+}
+
+/// Nearest tag reachable from HEAD. None value if there's no matching tag
+fn describe(
+    repo: &git2::Repository,
+    pattern: Option<&str>,
+) -> Result<Option<structs::GitDescribe>> {
+    let mut describe_options = git2::DescribeOptions::new();
+    describe_options.describe_tags();
+    if let Some(pattern) = pattern {
+        describe_options.pattern(pattern);
+    }
+
+    let describe = match repo.describe(&describe_options) {
+        Ok(describe) => describe,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut format_options = git2::DescribeFormatOptions::new();
+    format_options.always_use_long_format(true);
+
+    Ok(parse_describe(&describe.format(Some(&format_options))?))
+}
+
+/// Splits long `git describe` output "TAG-DISTANCE-gHASH". Tag itself may contain dashes.
+fn parse_describe(value: &str) -> Option<structs::GitDescribe> {
+    let mut parts = value.rsplitn(3, '-');
+    let _hash = parts.next()?.strip_prefix('g')?;
+    let distance = parts.next()?.parse().ok()?;
+    let tag = parts.next()?;
+
+    Some(structs::GitDescribe {
+        tag: tag.to_string(),
+        distance,
+    })
+}
+
+fn repo_state(repo: &git2::Repository) -> Result<Option<structs::GitRepoState>> {
+    let Some(operation) = git_operation(repo.state()) else {
+        return Ok(None);
+    };
+
+    let progress = progress_files(operation).and_then(|(folder, step_file, total_file)| {
+        state_progress(&repo.path().join(folder), step_file, total_file)
+    });
+
+    Ok(Some(structs::GitRepoState {
+        operation,
+        progress,
+    }))
+}
+
+fn git_operation(state: git2::RepositoryState) -> Option<structs::GitOperation> {
+    match state {
+        git2::RepositoryState::Clean => None,
+        git2::RepositoryState::Merge => Some(structs::GitOperation::Merge),
+        git2::RepositoryState::Revert | git2::RepositoryState::RevertSequence => {
+            Some(structs::GitOperation::Revert)
+        }
+        git2::RepositoryState::CherryPick | git2::RepositoryState::CherryPickSequence => {
+            Some(structs::GitOperation::CherryPick)
+        }
+        git2::RepositoryState::Bisect => Some(structs::GitOperation::Bisect),
+        git2::RepositoryState::Rebase => Some(structs::GitOperation::Rebase),
+        git2::RepositoryState::RebaseInteractive => Some(structs::GitOperation::RebaseInteractive),
+        git2::RepositoryState::RebaseMerge => Some(structs::GitOperation::RebaseMerge),
+        git2::RepositoryState::ApplyMailbox => Some(structs::GitOperation::ApplyMailbox),
+        git2::RepositoryState::ApplyMailboxOrRebase => {
+            Some(structs::GitOperation::ApplyMailboxOrRebase)
+        }
+    }
+}
+
+/// Folder and files of step counter, stored the same way git-prompt.sh reads them
+fn progress_files(
+    operation: structs::GitOperation,
+) -> Option<(&'static str, &'static str, &'static str)> {
+    match operation {
+        structs::GitOperation::RebaseInteractive | structs::GitOperation::RebaseMerge => {
+            Some(("rebase-merge", "msgnum", "end"))
+        }
+        structs::GitOperation::Rebase
+        | structs::GitOperation::ApplyMailbox
+        | structs::GitOperation::ApplyMailboxOrRebase => Some(("rebase-apply", "next", "last")),
+        _ => None,
+    }
+}
+
+fn state_progress(folder: &Path, step_file: &str, total_file: &str) -> Option<(usize, usize)> {
+    let read_number = |name: &str| -> Option<usize> {
+        std::fs::read_to_string(folder.join(name))
+            .ok_or_log()
+            .and_then(|v| v.trim().parse().ok())
+    };
+
+    Some((read_number(step_file)?, read_number(total_file)?))
+}
+
+fn stash_count(repo: &mut git2::Repository) -> Result<usize> {
+    let mut count = 0;
+    repo.stash_foreach(|_, _, _| {
+        count += 1;
+        true
+    })?;
+    Ok(count)
+}
+
+/// Line changes of staged and unstaged files against HEAD
+fn diff_stats(repo: &git2::Repository, include_submodules: bool) -> Result<structs::GitDiffStats> {
+    // Unborn branch has no tree, everything in index is new
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(err) if err.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut diff_options = git2::DiffOptions::new();
+    diff_options.ignore_submodules(!include_submodules);
+
+    let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_options))?;
+    let stats = diff.stats()?;
+
+    Ok(structs::GitDiffStats {
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+/// Summary of submodule states. None value if the repository has no submodules
+fn submodule_summary(repo: &git2::Repository) -> Result<Option<structs::GitSubmoduleSummary>> {
+    let submodules = repo.submodules()?;
+    if submodules.is_empty() {
+        return Ok(None);
+    }
+
+    let dirty = git2::SubmoduleStatus::WD_INDEX_MODIFIED
+        | git2::SubmoduleStatus::WD_WD_MODIFIED
+        | git2::SubmoduleStatus::WD_UNTRACKED;
+
+    let mut summary = structs::GitSubmoduleSummary::default();
+    for submodule in &submodules {
+        let Some(name) = submodule.name() else {
+            continue;
+        };
+        // Unspecified ignore rule respects `submodule.<name>.ignore` setting
+        let status = repo.submodule_status(name, git2::SubmoduleIgnore::Unspecified)?;
+
+        if status.is_wd_uninitialized() {
+            summary.uninitialized += 1;
+            continue;
+        }
+        if status.is_wd_modified() {
+            summary.out_of_date += 1;
+        }
+        if status.intersects(dirty) {
+            summary.dirty += 1;
+        }
+    }
+    Ok(Some(summary))
+}
+
 fn file_status(
     repo: &git2::Repository,
     options: &GetGitInfoOptionsInternal,
@@ -171,54 +802,239 @@ fn file_status(
     status_options.exclude_submodules(!options.include_submodules);
     status_options.include_ignored(false);
     status_options.include_unreadable(false);
-    status_options.include_untracked(options.include_untracked);
+    status_options.include_untracked(options.untracked_files != structs::UntrackedFiles::No);
+    status_options.recurse_untracked_dirs(options.untracked_files == structs::UntrackedFiles::All);
 
     let statuses = repo.statuses(Some(status_options))?;
 
-    let statuses_all = statuses
-        .iter()
-        .map(|s| s.status())
-        .reduce(|a, b| a.union(b))
-        .unwrap_or(git2::Status::empty());
-
-    let mut conflict = false;
-    let mut staged = false;
-    let mut unstaged = false;
-    let mut untracked = false;
-    let mut typechange = false;
-
-    for status in statuses_all {
-        match status {
-            git2::Status::CURRENT => conflict = true,
-            git2::Status::INDEX_NEW => staged = true,
-            git2::Status::INDEX_MODIFIED => staged = true,
-            git2::Status::INDEX_DELETED => staged = true,
-            git2::Status::INDEX_RENAMED => staged = true,
-            git2::Status::INDEX_TYPECHANGE => staged = true,
-            git2::Status::WT_NEW => untracked = true,
-            git2::Status::WT_MODIFIED => unstaged = true,
-            git2::Status::WT_DELETED => unstaged = true,
-            git2::Status::WT_TYPECHANGE => typechange = true,
-            git2::Status::WT_RENAMED => unstaged = true,
-            git2::Status::IGNORED => (),
-            git2::Status::CONFLICTED => conflict = true,
-            _ => (),
+    let mut result = structs::GitFileStatus::default();
+    let mut index = None;
+
+    for entry in statuses.iter() {
+        let mut status = entry.status();
+        // Files outside of sparse checkout are missing from workdir on purpose
+        if status.is_wt_deleted() && is_skip_worktree(repo, &mut index, entry.path())? {
+            status.remove(git2::Status::WT_DELETED);
+        }
+
+        for category in status_categories(status) {
+            *category.counter(&mut result) += 1;
         }
     }
 
-    Ok(structs::GitFileStatus {
-        conflict,
-        untracked,
-        typechange,
-        unstaged,
-        staged,
+    if result.conflicted > 0 {
+        result.conflicts = index_conflicts(repo)?;
+    }
+
+    Ok(result)
+}
+
+/// First line of every Git LFS pointer file
+const LFS_POINTER_HEADER: &[u8] = b"version https://git-lfs.github.com/spec/v1";
+
+/// Pointer files are small text files, bigger files are real content
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+
+/// Git LFS is in use if any root attribute assigns its filter
+fn uses_lfs(repo: &git2::Repository) -> bool {
+    repo.workdir()
+        .and_then(|w| fs::read_to_string(w.join(".gitattributes")).ok())
+        .is_some_and(|a| a.contains("filter=lfs"))
+}
+
+fn lfs_status(repo: &git2::Repository) -> Result<structs::GitLfsStatus> {
+    let workdir = repo.workdir().ok_or("bare repository has no LFS files")?;
+    let index = repo.index()?;
+    let mut result = structs::GitLfsStatus::default();
+
+    for entry in index.iter() {
+        let Ok(path) = std::str::from_utf8(&entry.path) else {
+            continue;
+        };
+        // Attributes are cached in memory, only files tracked by LFS are touched on disk
+        let filter = repo.get_attr(
+            Path::new(path),
+            "filter",
+            git2::AttrCheckFlags::FILE_THEN_INDEX,
+        )?;
+        if filter != Some("lfs") {
+            continue;
+        }
+
+        if is_lfs_pointer(&workdir.join(path)) {
+            result.pointers += 1;
+        }
+    }
+
+    // Git LFS caches locks created by current user only
+    result.locks = match fs::read(repo.commondir().join("lfs/lockcache.db")) {
+        Ok(content) => lfs_lock_count(&content),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(result)
+}
+
+/// Unsmudged file still holding pointer text instead of its content
+fn is_lfs_pointer(file: &Path) -> bool {
+    if fs::metadata(file).map_or(true, |m| m.len() > LFS_POINTER_MAX_SIZE) {
+        return false;
+    }
+
+    let mut header = [0; LFS_POINTER_HEADER.len()];
+    fs::File::open(file)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .is_ok_and(|_| header == LFS_POINTER_HEADER)
+}
+
+/// Lock cache is a gob encoded map where each lock is stored by path and by "_lockid_" prefixed id
+fn lfs_lock_count(content: &[u8]) -> usize {
+    const ID_PREFIX: &[u8] = b"_lockid_";
+    content
+        .windows(ID_PREFIX.len())
+        .filter(|w| *w == ID_PREFIX)
+        .count()
+}
+
+/// Index is loaded on first use, most statuses have no deleted files
+fn is_skip_worktree(
+    repo: &git2::Repository,
+    index: &mut Option<git2::Index>,
+    path: Option<&str>,
+) -> Result<bool> {
+    let Some(path) = path else {
+        return Ok(false);
+    };
+    let index = match index {
+        Some(index) => index,
+        None => index.insert(repo.index()?),
+    };
+
+    Ok(index.get_path(Path::new(path), 0).is_some_and(|e| {
+        git2::IndexEntryExtendedFlag::from_bits_truncate(e.flags_extended).is_skip_worktree()
+    }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusCategory {
+    Conflicted,
+    Staged,
+    Unstaged,
+    Untracked,
+    Typechange,
+    Renamed,
+    Deleted,
+}
+
+impl StatusCategory {
+    const ALL: [StatusCategory; 7] = [
+        StatusCategory::Conflicted,
+        StatusCategory::Staged,
+        StatusCategory::Unstaged,
+        StatusCategory::Untracked,
+        StatusCategory::Typechange,
+        StatusCategory::Renamed,
+        StatusCategory::Deleted,
+    ];
+
+    fn counter(self, status: &mut structs::GitFileStatus) -> &mut usize {
+        match self {
+            StatusCategory::Conflicted => &mut status.conflicted,
+            StatusCategory::Staged => &mut status.staged,
+            StatusCategory::Unstaged => &mut status.unstaged,
+            StatusCategory::Untracked => &mut status.untracked,
+            StatusCategory::Typechange => &mut status.typechange,
+            StatusCategory::Renamed => &mut status.renamed,
+            StatusCategory::Deleted => &mut status.deleted,
+        }
+    }
+}
+
+/// Status flags and the categories each of them is counted in
+const STATUS_CATEGORIES: [(git2::Status, StatusCategory); 15] = [
+    (git2::Status::INDEX_NEW, StatusCategory::Staged),
+    (git2::Status::INDEX_MODIFIED, StatusCategory::Staged),
+    (git2::Status::INDEX_DELETED, StatusCategory::Staged),
+    (git2::Status::INDEX_DELETED, StatusCategory::Deleted),
+    (git2::Status::INDEX_RENAMED, StatusCategory::Staged),
+    (git2::Status::INDEX_RENAMED, StatusCategory::Renamed),
+    (git2::Status::INDEX_TYPECHANGE, StatusCategory::Staged),
+    (git2::Status::WT_NEW, StatusCategory::Untracked),
+    (git2::Status::WT_MODIFIED, StatusCategory::Unstaged),
+    (git2::Status::WT_DELETED, StatusCategory::Unstaged),
+    (git2::Status::WT_DELETED, StatusCategory::Deleted),
+    (git2::Status::WT_TYPECHANGE, StatusCategory::Typechange),
+    (git2::Status::WT_RENAMED, StatusCategory::Unstaged),
+    (git2::Status::WT_RENAMED, StatusCategory::Renamed),
+    (git2::Status::CONFLICTED, StatusCategory::Conflicted),
+];
+
+/// Categories a single file is counted in. Each category is reported at most once.
+fn status_categories(status: git2::Status) -> impl Iterator<Item = StatusCategory> {
+    StatusCategory::ALL.into_iter().filter(move |category| {
+        STATUS_CATEGORIES
+            .iter()
+            .any(|(flag, c)| c == category && status.intersects(*flag))
     })
 }
 
+fn index_conflicts(repo: &git2::Repository) -> Result<structs::GitConflicts> {
+    let mut conflicts = structs::GitConflicts::default();
+
+    for conflict in repo.index()?.conflicts()? {
+        let conflict = conflict?;
+        let counter = conflict_counter(
+            &mut conflicts,
+            conflict.ancestor.is_some(),
+            conflict.our.is_some(),
+            conflict.their.is_some(),
+        );
+        if let Some(counter) = counter {
+            *counter += 1;
+        }
+    }
+
+    Ok(conflicts)
+}
+
+/// Conflict kind by the stages present in the index: ancestor, ours and theirs
+fn conflict_counter(
+    conflicts: &mut structs::GitConflicts,
+    ancestor: bool,
+    our: bool,
+    their: bool,
+) -> Option<&mut usize> {
+    match (ancestor, our, their) {
+        (true, true, true) => Some(&mut conflicts.both_modified),
+        (false, true, true) => Some(&mut conflicts.both_added),
+        (true, false, false) => Some(&mut conflicts.both_deleted),
+        (false, true, false) => Some(&mut conflicts.added_by_us),
+        (false, false, true) => Some(&mut conflicts.added_by_them),
+        (true, false, true) => Some(&mut conflicts.deleted_by_us),
+        (true, true, false) => Some(&mut conflicts.deleted_by_them),
+        (false, false, false) => None,
+    }
+}
+
 fn graph_ahead_behind(
     repo: &git2::Repository,
     head: &Option<GitHeadInfoInternal>,
 ) -> Result<structs::GitBranchAheadBehind> {
+    let (head_oid, tracking_oid) = tracking_oids(repo, head)?;
+    let ahead_behind = repo.graph_ahead_behind(head_oid, tracking_oid)?;
+
+    Ok(structs::GitBranchAheadBehind {
+        ahead: ahead_behind.0,
+        behind: ahead_behind.1,
+    })
+}
+
+/// Commits of HEAD and its tracking branch
+fn tracking_oids(
+    repo: &git2::Repository,
+    head: &Option<GitHeadInfoInternal>,
+) -> Result<(git2::Oid, git2::Oid)> {
     let reference: Option<&String> = head.as_ref().and_then(|h| h.reference_name.as_ref());
     let head_oid: Option<&git2::Oid> = head.as_ref().and_then(|h| h.oid.as_ref());
 
@@ -240,49 +1056,557 @@ fn graph_ahead_behind(
         return Err("tracking branch {:?} has no oid".into());
     }
 
-    let ahead_behind = repo.graph_ahead_behind(*head_oid.unwrap(), tracking_oid.unwrap())?;
+    Ok((*head_oid.unwrap(), tracking_oid.unwrap()))
+}
 
-    Ok(structs::GitBranchAheadBehind {
+/// Ahead/behind information against the branch `git push` updates, when it differs from
+/// the tracking branch, as in fork-based workflows
+fn push_ahead_behind(
+    repo: &git2::Repository,
+    head: &Option<GitHeadInfoInternal>,
+) -> Result<Option<structs::GitBranchAheadBehind>> {
+    let reference = head.as_ref().and_then(|h| h.reference_name.as_deref());
+    let head_oid = head.as_ref().and_then(|h| h.oid);
+
+    let (Some(reference), Some(head_oid)) = (reference, head_oid) else {
+        return Err("push branch doesn't exist".into());
+    };
+    let Some(branch) = reference.strip_prefix("refs/heads/") else {
+        return Ok(None);
+    };
+
+    let config = repo.config()?.snapshot()?;
+    let config_str = |name: String| config.get_str(&name).ok().map(String::from);
+
+    let upstream_remote = config_str(format!("branch.{}.remote", branch));
+    let push_remote = config_str(format!("branch.{}.pushRemote", branch))
+        .or_else(|| config_str("remote.pushDefault".to_string()))
+        .or_else(|| upstream_remote.clone())
+        .unwrap_or_else(|| "origin".to_string());
+    let push_default = config_str("push.default".to_string());
+
+    let push_reference = push_reference_name(
+        branch,
+        upstream_remote.as_deref(),
+        &push_remote,
+        push_default.as_deref().unwrap_or("simple"),
+    );
+    let upstream_reference = repo
+        .branch_upstream_name(reference)
+        .ok()
+        .and_then(|b| b.as_str().map(String::from));
+
+    let Some(push_reference) = push_reference.filter(|p| Some(p) != upstream_reference.as_ref())
+    else {
+        return Ok(None);
+    };
+
+    let push_oid = repo.refname_to_id(&push_reference)?;
+    let ahead_behind = repo.graph_ahead_behind(head_oid, push_oid)?;
+
+    Ok(Some(structs::GitBranchAheadBehind {
         ahead: ahead_behind.0,
         behind: ahead_behind.1,
-    })
+    }))
+}
+
+/// Ahead/behind information against base branch. None value if HEAD is the base branch itself
+fn base_ahead_behind(
+    repo: &git2::Repository,
+    head: &Option<GitHeadInfoInternal>,
+    base: &str,
+) -> Result<Option<structs::GitBaseAheadBehind>> {
+    let head_oid = head
+        .as_ref()
+        .and_then(|h| h.oid)
+        .ok_or("HEAD has no commit to compare with base branch")?;
+
+    let base_reference = match base {
+        "auto" => {
+            let origin_head = repo.find_reference("refs/remotes/origin/HEAD")?;
+            let target = origin_head
+                .symbolic_target()
+                .ok_or("origin/HEAD isn't a symbolic reference")?;
+            repo.find_reference(target)?
+        }
+        _ => repo.resolve_reference_from_short_name(base)?,
+    };
+
+    let base_oid = base_reference.peel_to_commit()?.id();
+    if base_oid == head_oid {
+        return Ok(None);
+    }
+
+    let ahead_behind = repo.graph_ahead_behind(head_oid, base_oid)?;
+
+    Ok(Some(structs::GitBaseAheadBehind {
+        reference_short: base_reference.shorthand().unwrap_or(base).to_string(),
+        ahead: ahead_behind.0,
+        behind: ahead_behind.1,
+    }))
+}
+
+/// Remote-tracking reference `git push` without arguments updates, following `push.default`.
+/// None value means push goes to the tracking branch or doesn't happen at all.
+fn push_reference_name(
+    branch: &str,
+    upstream_remote: Option<&str>,
+    push_remote: &str,
+    push_default: &str,
+) -> Option<String> {
+    let triangular = upstream_remote != Some(push_remote);
+
+    match push_default {
+        "nothing" | "upstream" | "tracking" => None,
+        "simple" if !triangular => None,
+        _ => Some(format!("refs/remotes/{}/{}", push_remote, branch)),
+    }
+}
+
+/// Options which change cached file status and ahead/behind information
+fn cache_options_key(
+    input_options: &structs::GetGitInfoOptions,
+    options: &GetGitInfoOptionsInternal,
+) -> String {
+    format!(
+        "{}:{}:{:?}:{}:{}",
+        input_options.reference_name,
+        options.include_submodules,
+        options.untracked_files,
+        options.include_ahead_behind,
+        options.include_workdir_stats,
+    )
 }
 
 fn configuration_overrided(
-    path: &Path,
+    repo: &git2::Repository,
     git_info_options: &structs::GetGitInfoOptions,
 ) -> Result<GetGitInfoOptionsInternal> {
-    let repo = git2::Repository::open(path)?;
     let config = repo.config()?.snapshot()?;
+    let timeout = config_option_var(&config, "timeout-ms", git_info_options.timeout_ms)
+        .map(Duration::from_millis);
 
     Ok(GetGitInfoOptionsInternal {
-        include_submodules: config_bool_var(
+        include_submodules: config_var(
             &config,
             "include-submodules",
             git_info_options.include_submodules,
         ),
-        include_untracked: config_bool_var(
-            &config,
-            "include-untracked",
-            git_info_options.include_untracked,
-        ),
-        refresh_status: config_bool_var(&config, "refresh-status", git_info_options.refresh_status),
-        include_ahead_behind: config_bool_var(
+        untracked_files: config_untracked_files_var(&config, git_info_options.untracked_files),
+        // Worker abandoned when time runs out could leave index.lock behind
+        refresh_status: timeout.is_none()
+            && config_var(&config, "refresh-status", git_info_options.refresh_status),
+        include_ahead_behind: config_var(
             &config,
             "include-ahead-behind",
             git_info_options.include_ahead_behind,
         ),
-        include_workdir_stats: config_bool_var(
+        include_workdir_stats: config_var(
             &config,
             "include-workdir-stats",
             git_info_options.include_workdir_stats,
         ),
+        include_diff_stats: config_var(
+            &config,
+            "include-diff-stats",
+            git_info_options.include_diff_stats,
+        ),
+        timeout,
+        use_cache: config_var(&config, "use-cache", git_info_options.use_cache),
+        base_reference: config_option_var(
+            &config,
+            "base-reference",
+            git_info_options.base_reference.map(String::from),
+        ),
+        describe_pattern: config_option_var(
+            &config,
+            "describe-pattern",
+            git_info_options.describe_pattern.map(String::from),
+        ),
+        abbrev: config_abbrev_var(&config, git_info_options.abbrev),
+        self_hosted_pattern: config_option_var(
+            &config,
+            "self-hosted-pattern",
+            git_info_options.self_hosted_pattern.map(String::from),
+        ),
     })
 }
 
-#[inline]
-fn config_bool_var(config: &git2::Config, name: &'static str, default_value: bool) -> bool {
-    config
-        .get_bool(format!("{}.{}", env!("CARGO_BIN_NAME"), name).as_str())
-        .unwrap_or(default_value)
+/// Mode is taken from `untracked-files`, then command line, then `status.showUntrackedFiles`.
+/// `include-untracked` switches untracked files off or back on
+fn config_untracked_files_var(
+    config: &git2::Config,
+    default_value: Option<structs::UntrackedFiles>,
+) -> structs::UntrackedFiles {
+    let untracked_files = config_option_var(config, "untracked-files", default_value)
+        .unwrap_or_else(|| {
+            config
+                .get_string("status.showUntrackedFiles")
+                .ok()
+                .and_then(|v| {
+                    parse_untracked_files(&v)
+                        .ok_or_else(|| git_config::invalid("status.showUntrackedFiles", &v))
+                        .ok_or_log()
+                })
+                .unwrap_or_default()
+        });
+
+    match (
+        config_option_var(config, "include-untracked", None),
+        untracked_files,
+    ) {
+        (Some(false), _) => structs::UntrackedFiles::No,
+        (Some(true), structs::UntrackedFiles::No) => structs::UntrackedFiles::Normal,
+        (_, mode) => mode,
+    }
+}
+
+/// Parses `status.showUntrackedFiles`, which also accepts git boolean values
+fn parse_untracked_files(value: &str) -> Option<structs::UntrackedFiles> {
+    match value.to_ascii_lowercase().as_str() {
+        "no" | "false" | "off" | "0" => Some(structs::UntrackedFiles::No),
+        "normal" | "yes" | "true" | "on" | "1" => Some(structs::UntrackedFiles::Normal),
+        "all" => Some(structs::UntrackedFiles::All),
+        _ => None,
+    }
+}
+
+/// `abbrev` is limited the same way as its command line argument
+fn config_abbrev_var(config: &git2::Config, default_value: Option<u64>) -> Option<u64> {
+    match config_option_var::<u64>(config, "abbrev", None) {
+        Some(abbrev) if (4..=40).contains(&abbrev) => Some(abbrev),
+        Some(abbrev) => {
+            git_config::invalid(&git_config::key("abbrev"), &abbrev.to_string()).log();
+            default_value
+        }
+        None => default_value,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        config_untracked_files_var, conflict_counter, git_operation, is_submodule_git_dir,
+        lfs_lock_count, parse_describe, parse_untracked_files, progress_files, push_reference_name,
+        short_id, sparse_pattern_count, state_progress, status_categories, StatusCategory,
+    };
+    use crate::structs;
+    use crate::test_util::TestDir;
+    use rstest::rstest;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    #[rstest]
+    #[case(5)]
+    #[case(10)]
+    fn short_id_core_abbrev_test(#[case] core_abbrev: i32) {
+        let dir = TestDir::new(&format!("abbrev-{}", core_abbrev));
+        let repo = git2::Repository::init(&dir).unwrap();
+        repo.config()
+            .unwrap()
+            .set_i32("core.abbrev", core_abbrev)
+            .unwrap();
+        let oid = repo.blob(b"content").unwrap();
+
+        let short = short_id(&repo, oid, None).unwrap();
+        assert_eq!(short.len(), core_abbrev as usize);
+        assert!(oid.to_string().starts_with(&short));
+    }
+
+    #[test]
+    fn short_id_unique_prefix_test() {
+        let dir = TestDir::new("abbrev-collision");
+        let repo = git2::Repository::init(&dir).unwrap();
+
+        // Blobs are written until two of them share first 4 hex digits
+        let mut prefixes = HashMap::new();
+        let (first, second) = (0..)
+            .find_map(|i| {
+                let oid = repo.blob(format!("blob {}", i).as_bytes()).unwrap();
+                prefixes
+                    .insert(oid.to_string()[..4].to_string(), oid)
+                    .map(|other| (other, oid))
+            })
+            .unwrap();
+
+        let first_short = short_id(&repo, first, Some(4)).unwrap();
+        let second_short = short_id(&repo, second, Some(4)).unwrap();
+
+        assert!(first_short.len() > 4);
+        assert!(first.to_string().starts_with(&first_short));
+        assert!(second.to_string().starts_with(&second_short));
+        assert_ne!(first_short, second_short);
+        assert_eq!(
+            repo.find_object_by_prefix(&first_short, None).unwrap().id(),
+            first
+        );
+
+        // Longer minimal length is kept as is
+        assert_eq!(short_id(&repo, first, Some(12)).unwrap().len(), 12);
+    }
+
+    #[rstest]
+    #[case(git2::RepositoryState::Clean, None)]
+    #[case(git2::RepositoryState::Merge, Some(structs::GitOperation::Merge))]
+    #[case(git2::RepositoryState::Revert, Some(structs::GitOperation::Revert))]
+    #[case(
+        git2::RepositoryState::RevertSequence,
+        Some(structs::GitOperation::Revert)
+    )]
+    #[case(
+        git2::RepositoryState::CherryPick,
+        Some(structs::GitOperation::CherryPick)
+    )]
+    #[case(
+        git2::RepositoryState::CherryPickSequence,
+        Some(structs::GitOperation::CherryPick)
+    )]
+    #[case(git2::RepositoryState::Bisect, Some(structs::GitOperation::Bisect))]
+    #[case(git2::RepositoryState::Rebase, Some(structs::GitOperation::Rebase))]
+    #[case(
+        git2::RepositoryState::RebaseInteractive,
+        Some(structs::GitOperation::RebaseInteractive)
+    )]
+    #[case(
+        git2::RepositoryState::RebaseMerge,
+        Some(structs::GitOperation::RebaseMerge)
+    )]
+    #[case(
+        git2::RepositoryState::ApplyMailbox,
+        Some(structs::GitOperation::ApplyMailbox)
+    )]
+    #[case(
+        git2::RepositoryState::ApplyMailboxOrRebase,
+        Some(structs::GitOperation::ApplyMailboxOrRebase)
+    )]
+    fn git_operation_test(
+        #[case] state: git2::RepositoryState,
+        #[case] expected: Option<structs::GitOperation>,
+    ) {
+        assert_eq!(git_operation(state), expected);
+    }
+
+    #[rstest]
+    #[case(structs::GitOperation::RebaseInteractive, Some(("rebase-merge", "msgnum", "end")))]
+    #[case(structs::GitOperation::RebaseMerge, Some(("rebase-merge", "msgnum", "end")))]
+    #[case(structs::GitOperation::Rebase, Some(("rebase-apply", "next", "last")))]
+    #[case(structs::GitOperation::ApplyMailbox, Some(("rebase-apply", "next", "last")))]
+    #[case(
+        structs::GitOperation::ApplyMailboxOrRebase,
+        Some(("rebase-apply", "next", "last"))
+    )]
+    #[case(structs::GitOperation::Merge, None)]
+    #[case(structs::GitOperation::Bisect, None)]
+    fn progress_files_test(
+        #[case] operation: structs::GitOperation,
+        #[case] expected: Option<(&str, &str, &str)>,
+    ) {
+        assert_eq!(progress_files(operation), expected);
+    }
+
+    #[rstest]
+    #[case("rebase-merge", &[("msgnum", "2\n"), ("end", "5\n")], "msgnum", "end", Some((2, 5)))]
+    #[case("rebase-apply", &[("next", "3"), ("last", " 7 ")], "next", "last", Some((3, 7)))]
+    #[case("rebase-missing", &[("msgnum", "2\n")], "msgnum", "end", None)]
+    #[case("rebase-garbage", &[("next", "x"), ("last", "7")], "next", "last", None)]
+    fn state_progress_test(
+        #[case] folder: &str,
+        #[case] files: &[(&str, &str)],
+        #[case] step_file: &str,
+        #[case] total_file: &str,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        let folder = TestDir::new(folder);
+        for (name, content) in files {
+            std::fs::write(folder.join(name), content).unwrap();
+        }
+
+        assert_eq!(state_progress(&folder, step_file, total_file), expected);
+    }
+
+    #[rstest]
+    #[case("/*\n!/*/\n/b/\n!/b/*/\n/a/\n/b/c/\n", true, 2)]
+    #[case("/*\n!/*/\n", true, 0)]
+    #[case("/*\n!/*/\n/a/\n/a/b/\n", true, 2)]
+    #[case("# comment\n\n/docs/\n*.md\n!/docs/private/\n", false, 3)]
+    #[case("", false, 0)]
+    fn sparse_pattern_count_test(
+        #[case] content: &str,
+        #[case] cone: bool,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(sparse_pattern_count(content, cone), expected);
+    }
+
+    #[rstest]
+    #[case(b"", 0)]
+    #[case(b"\x0c\xff\x81\x04\x01\x02\xff\x82\x00", 0)]
+    #[case(b"\x08big.psd\x10_lockid_42\x07big.psd", 1)]
+    #[case(b"a.bin_lockid_1 b.bin_lockid_2", 2)]
+    fn lfs_lock_count_test(#[case] content: &[u8], #[case] expected: usize) {
+        assert_eq!(lfs_lock_count(content), expected);
+    }
+
+    #[rstest]
+    #[case("no", Some(structs::UntrackedFiles::No))]
+    #[case("false", Some(structs::UntrackedFiles::No))]
+    #[case("normal", Some(structs::UntrackedFiles::Normal))]
+    #[case("True", Some(structs::UntrackedFiles::Normal))]
+    #[case("all", Some(structs::UntrackedFiles::All))]
+    #[case("some", None)]
+    fn parse_untracked_files_test(
+        #[case] value: &str,
+        #[case] expected: Option<structs::UntrackedFiles>,
+    ) {
+        assert_eq!(parse_untracked_files(value), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "[status]\n\tshowUntrackedFiles = all\n",
+        Some(structs::UntrackedFiles::No),
+        structs::UntrackedFiles::No
+    )]
+    #[case(
+        "[status]\n\tshowUntrackedFiles = all\n",
+        None,
+        structs::UntrackedFiles::All
+    )]
+    #[case(
+        "[ilsore-format]\n\tuntracked-files = all\n",
+        Some(structs::UntrackedFiles::No),
+        structs::UntrackedFiles::All
+    )]
+    #[case(
+        "[ilsore-format]\n\tinclude-untracked = false\n",
+        Some(structs::UntrackedFiles::All),
+        structs::UntrackedFiles::No
+    )]
+    #[case(
+        "[status]\n\tshowUntrackedFiles = no\n",
+        None,
+        structs::UntrackedFiles::No
+    )]
+    #[case("", None, structs::UntrackedFiles::Normal)]
+    fn config_untracked_files_var_test(
+        #[case] content: &str,
+        #[case] command_line: Option<structs::UntrackedFiles>,
+        #[case] expected: structs::UntrackedFiles,
+    ) {
+        let folder = TestDir::new("untracked-files");
+        let file = folder.join("config");
+        std::fs::write(&file, content).unwrap();
+        let config = git2::Config::open(&file).unwrap().snapshot().unwrap();
+
+        assert_eq!(config_untracked_files_var(&config, command_line), expected);
+    }
+
+    #[rstest]
+    #[case("v1.4.2-3-gabcdef12", Some(("v1.4.2", 3)))]
+    #[case("v1.4.2-0-gabcdef12", Some(("v1.4.2", 0)))]
+    #[case("release-2024-01-12-gabcdef12", Some(("release-2024-01", 12)))]
+    #[case("v1.4.2", None)]
+    #[case("v1.4.2-x-gabcdef12", None)]
+    fn parse_describe_test(#[case] value: &str, #[case] expected: Option<(&str, usize)>) {
+        let result = parse_describe(value);
+        assert_eq!(
+            result.as_ref().map(|d| (d.tag.as_str(), d.distance)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(Some("origin"), "origin", "simple", None)]
+    #[case(
+        Some("upstream"),
+        "origin",
+        "simple",
+        Some("refs/remotes/origin/feature")
+    )]
+    #[case(None, "origin", "simple", Some("refs/remotes/origin/feature"))]
+    #[case(
+        Some("origin"),
+        "origin",
+        "current",
+        Some("refs/remotes/origin/feature")
+    )]
+    #[case(Some("upstream"), "fork", "current", Some("refs/remotes/fork/feature"))]
+    #[case(
+        Some("upstream"),
+        "fork",
+        "matching",
+        Some("refs/remotes/fork/feature")
+    )]
+    #[case(Some("upstream"), "fork", "upstream", None)]
+    #[case(Some("upstream"), "fork", "nothing", None)]
+    fn push_reference_name_test(
+        #[case] upstream_remote: Option<&str>,
+        #[case] push_remote: &str,
+        #[case] push_default: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        assert_eq!(
+            push_reference_name("feature", upstream_remote, push_remote, push_default).as_deref(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("/work/repo/.git", false)]
+    #[case("/work/repo/.git/worktrees/feature", false)]
+    #[case("/work/modules/repo/.git", false)]
+    #[case("/work/repo/.git/modules/lib", true)]
+    #[case("/work/repo/.git/modules/lib/modules/nested", true)]
+    #[case("/work/repo/.git/modules/group/lib", true)]
+    fn is_submodule_git_dir_test(#[case] git_dir: &str, #[case] expected: bool) {
+        assert_eq!(is_submodule_git_dir(Path::new(git_dir)), expected);
+    }
+
+    #[rstest]
+    #[case(git2::Status::CURRENT, vec![])]
+    #[case(git2::Status::IGNORED, vec![])]
+    #[case(git2::Status::INDEX_NEW, vec![StatusCategory::Staged])]
+    #[case(git2::Status::INDEX_MODIFIED, vec![StatusCategory::Staged])]
+    #[case(git2::Status::INDEX_DELETED, vec![StatusCategory::Staged, StatusCategory::Deleted])]
+    #[case(git2::Status::INDEX_RENAMED, vec![StatusCategory::Staged, StatusCategory::Renamed])]
+    #[case(git2::Status::INDEX_TYPECHANGE, vec![StatusCategory::Staged])]
+    #[case(git2::Status::WT_NEW, vec![StatusCategory::Untracked])]
+    #[case(git2::Status::WT_MODIFIED, vec![StatusCategory::Unstaged])]
+    #[case(git2::Status::WT_DELETED, vec![StatusCategory::Unstaged, StatusCategory::Deleted])]
+    #[case(git2::Status::WT_TYPECHANGE, vec![StatusCategory::Typechange])]
+    #[case(git2::Status::WT_RENAMED, vec![StatusCategory::Unstaged, StatusCategory::Renamed])]
+    #[case(git2::Status::CONFLICTED, vec![StatusCategory::Conflicted])]
+    #[case(
+        git2::Status::INDEX_MODIFIED | git2::Status::WT_MODIFIED,
+        vec![StatusCategory::Staged, StatusCategory::Unstaged]
+    )]
+    #[case(
+        git2::Status::INDEX_DELETED | git2::Status::WT_DELETED,
+        vec![StatusCategory::Staged, StatusCategory::Unstaged, StatusCategory::Deleted]
+    )]
+    fn status_categories_test(#[case] status: git2::Status, #[case] expected: Vec<StatusCategory>) {
+        assert_eq!(status_categories(status).collect::<Vec<_>>(), expected);
+    }
+
+    #[rstest]
+    #[case(true, true, true, structs::GitConflicts { both_modified: 1, ..Default::default() })]
+    #[case(false, true, true, structs::GitConflicts { both_added: 1, ..Default::default() })]
+    #[case(true, false, false, structs::GitConflicts { both_deleted: 1, ..Default::default() })]
+    #[case(false, true, false, structs::GitConflicts { added_by_us: 1, ..Default::default() })]
+    #[case(false, false, true, structs::GitConflicts { added_by_them: 1, ..Default::default() })]
+    #[case(true, false, true, structs::GitConflicts { deleted_by_us: 1, ..Default::default() })]
+    #[case(true, true, false, structs::GitConflicts { deleted_by_them: 1, ..Default::default() })]
+    #[case(false, false, false, structs::GitConflicts::default())]
+    fn conflict_counter_test(
+        #[case] ancestor: bool,
+        #[case] our: bool,
+        #[case] their: bool,
+        #[case] expected: structs::GitConflicts,
+    ) {
+        let mut conflicts = structs::GitConflicts::default();
+        if let Some(counter) = conflict_counter(&mut conflicts, ancestor, our, their) {
+            *counter += 1;
+        }
+        assert_eq!(conflicts, expected);
+    }
 }
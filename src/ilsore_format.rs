@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 
+use crate::date_time;
 use crate::structs;
+use crate::util;
 
 pub(crate) fn format_ilsore_no_color(
     data: &structs::ThemeData,
@@ -15,7 +17,10 @@ pub(crate) fn format_ilsore_no_color(
     );
     let python = data.python.as_ref().map(|v| format!("[{}]", v));
 
-    let git = data.git.as_ref().map(|v| format_ilsore_git(v, symbols));
+    let git = data
+        .git
+        .as_ref()
+        .map(|v| format_ilsore_git(v, symbols, &data.options));
 
     let last_status: Cow<str> = if data.last_exit_status != 0 {
         format!("[{}]", data.last_exit_status).into()
@@ -37,22 +42,51 @@ pub(crate) fn format_ilsore_no_color(
 fn format_ilsore_git(
     data: &structs::GitOutputOptions,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> Cow<'static, str> {
-    if data.head_info.is_none() {
-        return Cow::Borrowed("");
+    match (&data.head_info, data.timed_out) {
+        (None, false) => return Cow::Borrowed(""),
+        // Repository is known but time budget ran out before its branch was read
+        (None, true) => return format!("(Git: {})", symbols.git_status_unknown).into(),
+        _ => (),
     }
 
     format!(
-        "(Git: {} {})",
-        format_ilsore_git_head_info(&data.head_info, symbols)
+        "(Git: {}{}{}{}{} {}{}{}{}{}{}{})",
+        format_ilsore_git_remote(&data.remote, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_head_info(&data.head_info, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_repo_kind(data, symbols)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_clone(data, symbols)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_state(&data.repo_state, symbols)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_symbols(data, symbols, options),
+        format_ilsore_git_diff_stats(&data.diff_stats, symbols)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_base(&data.base_ahead_behind, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_submodules(&data.submodules, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_lfs(&data.lfs, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_stash(data.stash_count, symbols, options)
+            .as_deref()
+            .unwrap_or_default(),
+        format_ilsore_git_commit(&data.head_info, options)
             .as_deref()
             .unwrap_or_default(),
-        format_ilsore_git_symbols(
-            &data.head_info,
-            &data.file_status,
-            &data.branch_ahead_behind,
-            symbols
-        )
     )
     .into()
 }
@@ -61,58 +95,339 @@ fn format_ilsore_git(
 fn format_ilsore_git_head_info(
     head_info: &Option<structs::GitHeadInfo>,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> Option<String> {
     head_info.as_ref().and_then(|h| {
-        h.reference_short
-            .as_ref()
-            .map(|v| format!("{} {}", symbols.git_branch, v))
+        if let Some(describe) = h.describe.as_ref().filter(|_| h.detached) {
+            return Some(describe.to_string());
+        }
+
+        h.reference_display(options.git_reference_display)
+            .map(|v| {
+                let v = util::truncate_middle(
+                    v,
+                    options.git_reference_max_length,
+                    symbols.git_reference_ellipsis,
+                );
+                format!("{} {}", symbols.git_branch, v)
+            })
             .or(h.oid_short.as_ref().map(String::to_string))
     })
 }
 
 #[inline]
-fn format_ilsore_git_symbols(
+fn format_ilsore_git_remote(
+    remote: &Option<structs::GitRemote>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let remote = remote.as_ref().filter(|_| options.git_remote)?;
+    let provider = symbols.git_provider(remote.provider);
+
+    match remote.slug.as_deref().filter(|_| options.git_remote_slug) {
+        Some(slug) => Some(format!("{} {} ", provider, slug)),
+        None => Some(format!("{} ", provider)),
+    }
+}
+
+#[inline]
+fn format_ilsore_git_repo_kind(
+    data: &structs::GitOutputOptions,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    let symbol = symbols.git_repo_kind(data.repo_kind)?;
+    let worktree_name = data.worktree.as_ref().and_then(|w| w.name.as_deref());
+
+    Some(format!(" {}{}", symbol, worktree_name.unwrap_or_default()))
+}
+
+#[inline]
+fn format_ilsore_git_clone(
+    data: &structs::GitOutputOptions,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    let result = format!(
+        "{}{}{}",
+        symbol(data.shallow, symbols.git_repo_shallow),
+        symbol(data.partial_clone, symbols.git_repo_partial_clone),
+        data.sparse_checkout
+            .as_ref()
+            .map(|s| format!("{}{}", symbols.git_repo_sparse, s.patterns))
+            .unwrap_or_default(),
+    );
+    (!result.is_empty()).then(|| format!(" {}", result))
+}
+
+#[inline]
+fn format_ilsore_git_state(
+    repo_state: &Option<structs::GitRepoState>,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    repo_state.as_ref().map(|s| {
+        let operation = symbols.git_operation(s.operation);
+        match s.progress {
+            Some((step, total)) => format!(" {} {}/{}", operation, step, total),
+            None => format!(" {}", operation),
+        }
+    })
+}
+
+#[inline]
+fn format_ilsore_git_diff_stats(
+    diff_stats: &Option<structs::GitDiffStats>,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    match diff_stats {
+        Some(d) if d.insertions > 0 || d.deletions > 0 => Some(format!(
+            " {}{} {}{}",
+            symbols.git_diff_insertions, d.insertions, symbols.git_diff_deletions, d.deletions
+        )),
+        _ => None,
+    }
+}
+
+#[inline]
+fn format_ilsore_git_base(
+    base_ahead_behind: &Option<structs::GitBaseAheadBehind>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    base_ahead_behind.as_ref().map(|b| {
+        format!(
+            " {}{}{}{}",
+            symbols.git_base,
+            b.reference_short,
+            counter(b.ahead, symbols.git_is_ahead, options.git_count_limit).unwrap_or_default(),
+            counter(b.behind, symbols.git_is_behind, options.git_count_limit).unwrap_or_default(),
+        )
+    })
+}
+
+#[inline]
+fn format_ilsore_git_submodules(
+    submodules: &Option<structs::GitSubmoduleSummary>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let submodules = submodules.as_ref()?;
+    let result = [
+        counter(
+            submodules.uninitialized,
+            symbols.git_submodule_uninitialized,
+            options.git_count_limit,
+        ),
+        counter(
+            submodules.out_of_date,
+            symbols.git_submodule_out_of_date,
+            options.git_count_limit,
+        ),
+        counter(
+            submodules.dirty,
+            symbols.git_submodule_dirty,
+            options.git_count_limit,
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<String>();
+
+    (!result.is_empty()).then(|| format!(" {}", result))
+}
+
+#[inline]
+fn format_ilsore_git_lfs(
+    lfs: &Option<structs::GitLfsStatus>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let lfs = lfs.as_ref()?;
+    let result = [
+        counter(
+            lfs.pointers,
+            symbols.git_lfs_pointer,
+            options.git_count_limit,
+        ),
+        counter(lfs.locks, symbols.git_lfs_lock, options.git_count_limit),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<String>();
+
+    (!result.is_empty()).then(|| format!(" {}", result))
+}
+
+#[inline]
+fn format_ilsore_git_stash(
+    stash_count: Option<usize>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    match stash_count {
+        Some(0) | None => None,
+        Some(count) if options.git_stash_count => {
+            counter(count, symbols.git_has_stash, options.git_count_limit)
+        }
+        Some(_) => Some(symbols.git_has_stash.to_string()),
+    }
+}
+
+#[inline]
+fn format_ilsore_git_commit(
     head_info: &Option<structs::GitHeadInfo>,
-    file_status: &Option<structs::GitFileStatus>,
-    branch_ahead_behind: &Option<structs::GitBranchAheadBehind>,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let head_info = head_info.as_ref()?;
+    let age = head_info
+        .commit_time
+        .filter(|_| options.git_commit_age)
+        .map(|t| date_time::relative_age(t, chrono::Utc::now()));
+    let author = head_info
+        .commit_author
+        .as_deref()
+        .filter(|_| options.git_commit_author)
+        .map(util::initials);
+
+    let result = [age, author].into_iter().flatten().collect::<Vec<_>>();
+    (!result.is_empty()).then(|| format!(" {}", result.join(" ")))
+}
+
+#[inline]
+fn format_ilsore_git_symbols(
+    data: &structs::GitOutputOptions,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> String {
+    let unborn = data.head_info.as_ref().is_some_and(|b| b.unborn);
+    // Without finished lookup missing tracking branch is unknown, not absent
+    let no_upstream = data.upstream_checked && data.branch_ahead_behind.is_none();
+
     format!(
-        "{}{}{}{}{}{}{}{}{}",
+        "{}{}{}{}{}{}",
         symbol(
-            head_info.as_ref().map_or(false, |b| b.detached),
+            data.head_info.as_ref().is_some_and(|b| b.detached),
             symbols.git_branch_detached
         ),
-        symbol(branch_ahead_behind.is_none(), symbols.git_has_no_upstream),
-        symbol(
-            branch_ahead_behind.as_ref().map_or(false, |b| b.ahead > 0),
-            symbols.git_is_ahead
+        symbol(unborn, symbols.git_branch_unborn),
+        symbol(!unborn && no_upstream, symbols.git_has_no_upstream),
+        format_ilsore_git_ahead_behind(
+            &data.branch_ahead_behind,
+            &data.push_ahead_behind,
+            symbols,
+            options
         ),
-        symbol(
-            branch_ahead_behind.as_ref().map_or(false, |b| b.behind > 0),
-            symbols.git_is_behind
+        data.file_status
+            .as_ref()
+            .map(|f| format_ilsore_git_file_status(f, symbols, options))
+            .unwrap_or_default(),
+        symbol(data.timed_out, symbols.git_status_unknown),
+    )
+}
+
+#[inline]
+fn format_ilsore_git_ahead_behind(
+    branch_ahead_behind: &Option<structs::GitBranchAheadBehind>,
+    push_ahead_behind: &Option<structs::GitBranchAheadBehind>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> String {
+    let behind = branch_ahead_behind.as_ref().map_or(0, |b| b.behind);
+
+    // In triangular workflows commits are pushed to fork and pulled from upstream
+    let (ahead, ahead_symbol, behind_symbol) = match push_ahead_behind {
+        Some(push) => (
+            push.ahead,
+            symbols.git_is_ahead_push,
+            symbols.git_is_behind_upstream,
         ),
-        symbol(
-            file_status.as_ref().map_or(false, |b| b.conflict),
-            symbols.git_has_conflict
+        None => (
+            branch_ahead_behind.as_ref().map_or(0, |b| b.ahead),
+            symbols.git_is_ahead,
+            symbols.git_is_behind,
         ),
-        symbol(
-            file_status.as_ref().map_or(false, |b| b.untracked),
-            symbols.git_has_untracked
+    };
+
+    match options.git_ahead_behind_counts {
+        false => format!(
+            "{}{}",
+            symbol(ahead > 0, ahead_symbol),
+            symbol(behind > 0, behind_symbol),
         ),
-        symbol(
-            file_status.as_ref().map_or(false, |b| b.typechange),
-            symbols.git_has_typechange
+        true => format!(
+            "{}{}",
+            counter(ahead, ahead_symbol, options.git_count_limit).unwrap_or_default(),
+            counter(behind, behind_symbol, options.git_count_limit).unwrap_or_default(),
         ),
-        symbol(
-            file_status.as_ref().map_or(false, |b| b.unstaged),
-            symbols.git_has_unstaged
+    }
+}
+
+#[inline]
+fn format_ilsore_git_file_status(
+    file_status: &structs::GitFileStatus,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> String {
+    if !options.git_status_counts {
+        return format!(
+            "{}{}{}{}{}",
+            symbol(file_status.conflicted > 0, symbols.git_has_conflict),
+            symbol(file_status.untracked > 0, symbols.git_has_untracked),
+            symbol(file_status.typechange > 0, symbols.git_has_typechange),
+            symbol(file_status.unstaged > 0, symbols.git_has_unstaged),
+            symbol(file_status.staged > 0, symbols.git_has_staged),
+        );
+    }
+
+    let counters = [
+        counter(
+            file_status.conflicted,
+            symbols.git_has_conflict,
+            options.git_count_limit,
         ),
-        symbol(
-            file_status.as_ref().map_or(false, |b| b.staged),
-            symbols.git_has_staged
+        counter(
+            file_status.untracked,
+            symbols.git_has_untracked,
+            options.git_count_limit,
         ),
-    )
+        counter(
+            file_status.typechange,
+            symbols.git_has_typechange,
+            options.git_count_limit,
+        ),
+        counter(
+            file_status.renamed,
+            symbols.git_has_renamed,
+            options.git_count_limit,
+        ),
+        counter(
+            file_status.deleted,
+            symbols.git_has_deleted,
+            options.git_count_limit,
+        ),
+        counter(
+            file_status.unstaged,
+            symbols.git_has_unstaged,
+            options.git_count_limit,
+        ),
+        counter(
+            file_status.staged,
+            symbols.git_has_staged,
+            options.git_count_limit,
+        ),
+    ];
+
+    let result = counters.into_iter().flatten().collect::<Vec<_>>().join(" ");
+    match result.is_empty() {
+        true => result,
+        false => format!(" {}", result),
+    }
+}
+
+#[inline]
+fn counter(count: usize, symbol: &'static str, limit: usize) -> Option<String> {
+    match count {
+        0 => None,
+        _ => Some(format!("{}{}", symbol, util::capped_count(count, limit))),
+    }
 }
 
 #[inline]
@@ -122,3 +437,70 @@ fn symbol(present: bool, symbol: &'static str) -> &'static str {
         false => "",
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::format_ilsore_git;
+    use crate::structs;
+    use rstest::rstest;
+
+    fn git_output(
+        head_info: Option<structs::GitHeadInfo>,
+        upstream_checked: bool,
+        timed_out: bool,
+    ) -> structs::GitOutputOptions {
+        structs::GitOutputOptions {
+            head_info,
+            file_status: None,
+            branch_ahead_behind: None,
+            push_ahead_behind: None,
+            base_ahead_behind: None,
+            remote: None,
+            repo_state: None,
+            stash_count: None,
+            submodules: None,
+            diff_stats: None,
+            lfs: None,
+            timed_out,
+            upstream_checked,
+            repo_kind: structs::GitRepoKind::Main,
+            shallow: false,
+            partial_clone: false,
+            sparse_checkout: None,
+            worktree: None,
+        }
+    }
+
+    fn master() -> structs::GitHeadInfo {
+        structs::GitHeadInfo {
+            reference_name: Some("refs/heads/master".to_string()),
+            reference_short: Some("master".to_string()),
+            oid_short: Some("abcdef1".to_string()),
+            detached: false,
+            unborn: false,
+            describe: None,
+            commit_time: None,
+            commit_author: None,
+        }
+    }
+
+    #[rstest]
+    #[case(None, false, false, "")]
+    #[case(None, false, true, "(Git: ~)")]
+    #[case(Some(master()), false, true, "(Git:  master ~)")]
+    #[case(Some(master()), true, true, "(Git:  master &~)")]
+    #[case(Some(master()), true, false, "(Git:  master &)")]
+    fn format_ilsore_git_test(
+        #[case] head_info: Option<structs::GitHeadInfo>,
+        #[case] upstream_checked: bool,
+        #[case] timed_out: bool,
+        #[case] expected: &str,
+    ) {
+        let result = format_ilsore_git(
+            &git_output(head_info, upstream_checked, timed_out),
+            &structs::ThemeSymbols::ascii(),
+            &structs::ThemeOptions::default(),
+        );
+        assert_eq!(result, expected);
+    }
+}
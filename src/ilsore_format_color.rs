@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 
+use crate::date_time;
 use crate::structs;
+use crate::util;
 
 static RESET_COLOR: &str = "%{[0m%}";
 
@@ -29,7 +31,10 @@ pub(crate) fn format_ilsore_color(
         .as_ref()
         .map(|v| format!("[{}{}{RESET_COLOR}]", format_color_bold("42"), v));
 
-    let git = data.git.as_ref().map(|v| format_ilsore_git(v, symbols));
+    let git = data
+        .git
+        .as_ref()
+        .map(|v| format_ilsore_git(v, symbols, &data.options));
 
     let last_status: Cow<str> = if data.last_exit_status != 0 {
         format!(
@@ -69,29 +74,47 @@ fn format_color_bold(color: &str) -> String {
 fn format_ilsore_git(
     data: &structs::GitOutputOptions,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> Cow<'static, str> {
-    if data.head_info.is_none() {
-        return Cow::Borrowed("");
+    match (&data.head_info, data.timed_out) {
+        (None, false) => return Cow::Borrowed(""),
+        // Repository is known but time budget ran out before its branch was read
+        (None, true) => {
+            return format!(
+                "({}Git: {}{RESET_COLOR})",
+                format_color("magenta"),
+                symbol_bold(true, symbols.git_status_unknown, "208").unwrap_or_default()
+            )
+            .into()
+        }
+        _ => (),
     }
 
-    let git_info = vec![
-        data.head_info
-            .as_ref()
-            .and_then(|h| format_ilsore_git_branch(h, symbols))
-            .unwrap_or_default(),
-        format_ilsore_git_symbols(
-            &data.head_info,
-            &data.file_status,
-            &data.branch_ahead_behind,
-            symbols,
-        )
-        .unwrap_or_default(),
+    // Branch and symbols keep their place even when empty, other parts only when present
+    let git_info = [
+        format_ilsore_git_remote(&data.remote, symbols, options),
+        Some(
+            data.head_info
+                .as_ref()
+                .and_then(|h| format_ilsore_git_branch(h, symbols, options))
+                .unwrap_or_default(),
+        ),
+        format_ilsore_git_repo_kind(data, symbols),
+        format_ilsore_git_clone(data, symbols),
+        format_ilsore_git_state(&data.repo_state, symbols),
+        Some(format_ilsore_git_symbols(data, symbols, options).unwrap_or_default()),
+        format_ilsore_git_diff_stats(&data.diff_stats, symbols),
+        format_ilsore_git_base(&data.base_ahead_behind, symbols, options),
+        format_ilsore_git_submodules(&data.submodules, symbols, options),
+        format_ilsore_git_lfs(&data.lfs, symbols, options),
+        format_ilsore_git_stash(data.stash_count, symbols, options),
+        format_ilsore_git_commit(&data.head_info, options),
     ];
 
     format!(
         "({}Git: {}{RESET_COLOR})",
         format_color("magenta"),
-        git_info.join(" ")
+        git_info.into_iter().flatten().collect::<Vec<_>>().join(" ")
     )
     .into()
 }
@@ -100,66 +123,378 @@ fn format_ilsore_git(
 fn format_ilsore_git_branch(
     head_info: &structs::GitHeadInfo,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> Option<String> {
-    if head_info.reference_short.is_none() && head_info.oid_short.is_none() {
+    let reference = head_info.reference_display(options.git_reference_display);
+    if reference.is_none() && head_info.oid_short.is_none() {
         return None;
     };
-    if head_info.reference_short.is_none() || head_info.detached {
-        Some(format!(
-            "{}{}{RESET_COLOR}",
-            format_color_bold("201"),
-            head_info.oid_short.as_deref().unwrap_or_default()
-        ))
+    if reference.is_none() || head_info.detached {
+        // Nearest tag is more readable than a hash
+        let name = match &head_info.describe {
+            Some(describe) => describe.to_string(),
+            None => head_info.oid_short.clone().unwrap_or_default(),
+        };
+        Some(format!("{}{}{RESET_COLOR}", format_color_bold("201"), name))
     } else {
         Some(format!(
             "{}{} {}{RESET_COLOR}",
             format_color_bold("226"),
             symbols.git_branch,
-            head_info.reference_short.as_deref().unwrap_or_default()
+            util::truncate_middle(
+                reference.unwrap_or_default(),
+                options.git_reference_max_length,
+                symbols.git_reference_ellipsis
+            )
         ))
     }
 }
 
 #[inline]
-fn format_ilsore_git_symbols(
+fn format_ilsore_git_remote(
+    remote: &Option<structs::GitRemote>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let remote = remote.as_ref().filter(|_| options.git_remote)?;
+    let provider = symbols.git_provider(remote.provider);
+
+    match remote.slug.as_deref().filter(|_| options.git_remote_slug) {
+        Some(slug) => Some(format!(
+            "{}{} {}{RESET_COLOR}",
+            format_color("250"),
+            provider,
+            slug
+        )),
+        None => Some(format!("{}{}{RESET_COLOR}", format_color("250"), provider)),
+    }
+}
+
+#[inline]
+fn format_ilsore_git_repo_kind(
+    data: &structs::GitOutputOptions,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    let symbol = symbols.git_repo_kind(data.repo_kind)?;
+    let worktree_name = data.worktree.as_ref().and_then(|w| w.name.as_deref());
+
+    Some(format!(
+        "{}{}{}{RESET_COLOR}",
+        format_color_bold("45"),
+        symbol,
+        worktree_name.unwrap_or_default()
+    ))
+}
+
+#[inline]
+fn format_ilsore_git_clone(
+    data: &structs::GitOutputOptions,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    let result = vec![
+        symbol_bold(data.shallow, symbols.git_repo_shallow, "45"),
+        symbol_bold(data.partial_clone, symbols.git_repo_partial_clone, "45"),
+        data.sparse_checkout.as_ref().map(|s| {
+            format!(
+                "{}{}{}",
+                format_color_bold("45"),
+                symbols.git_repo_sparse,
+                s.patterns
+            )
+        }),
+    ]
+    .i_join();
+    (!result.is_empty()).then(|| format!("{}{RESET_COLOR}", result))
+}
+
+#[inline]
+fn format_ilsore_git_state(
+    repo_state: &Option<structs::GitRepoState>,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    repo_state.as_ref().map(|s| {
+        let operation = symbols.git_operation(s.operation);
+        match s.progress {
+            Some((step, total)) => format!(
+                "{}{} {}/{}{RESET_COLOR}",
+                format_color_bold("208"),
+                operation,
+                step,
+                total
+            ),
+            None => format!("{}{}{RESET_COLOR}", format_color_bold("208"), operation),
+        }
+    })
+}
+
+#[inline]
+fn format_ilsore_git_diff_stats(
+    diff_stats: &Option<structs::GitDiffStats>,
+    symbols: &structs::ThemeSymbols,
+) -> Option<String> {
+    match diff_stats {
+        Some(d) if d.insertions > 0 || d.deletions > 0 => Some(format!(
+            "{}{}{} {}{}{}{RESET_COLOR}",
+            format_color("green"),
+            symbols.git_diff_insertions,
+            d.insertions,
+            format_color("red"),
+            symbols.git_diff_deletions,
+            d.deletions
+        )),
+        _ => None,
+    }
+}
+
+#[inline]
+fn format_ilsore_git_base(
+    base_ahead_behind: &Option<structs::GitBaseAheadBehind>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    base_ahead_behind.as_ref().map(|b| {
+        format!(
+            "{}{}{}{}{RESET_COLOR}",
+            format_color("75"),
+            symbols.git_base,
+            b.reference_short,
+            vec![
+                counter_bold(
+                    b.ahead,
+                    symbols.git_is_ahead,
+                    "magenta",
+                    options.git_count_limit
+                ),
+                counter_bold(
+                    b.behind,
+                    symbols.git_is_behind,
+                    "green",
+                    options.git_count_limit
+                ),
+            ]
+            .i_join(),
+        )
+    })
+}
+
+#[inline]
+fn format_ilsore_git_submodules(
+    submodules: &Option<structs::GitSubmoduleSummary>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let submodules = submodules.as_ref()?;
+    let result = vec![
+        counter_bold(
+            submodules.uninitialized,
+            symbols.git_submodule_uninitialized,
+            "208",
+            options.git_count_limit,
+        ),
+        counter_bold(
+            submodules.out_of_date,
+            symbols.git_submodule_out_of_date,
+            "208",
+            options.git_count_limit,
+        ),
+        counter_bold(
+            submodules.dirty,
+            symbols.git_submodule_dirty,
+            "red",
+            options.git_count_limit,
+        ),
+    ]
+    .i_join();
+
+    (!result.is_empty()).then(|| format!("{}{RESET_COLOR}", result))
+}
+
+#[inline]
+fn format_ilsore_git_lfs(
+    lfs: &Option<structs::GitLfsStatus>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let lfs = lfs.as_ref()?;
+    let result = vec![
+        counter_bold(
+            lfs.pointers,
+            symbols.git_lfs_pointer,
+            "208",
+            options.git_count_limit,
+        ),
+        counter_bold(
+            lfs.locks,
+            symbols.git_lfs_lock,
+            "39",
+            options.git_count_limit,
+        ),
+    ]
+    .i_join();
+
+    (!result.is_empty()).then(|| format!("{}{RESET_COLOR}", result))
+}
+
+#[inline]
+fn format_ilsore_git_stash(
+    stash_count: Option<usize>,
+    symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    match stash_count {
+        Some(0) | None => None,
+        Some(count) if options.git_stash_count => Some(format!(
+            "{}{}{}{RESET_COLOR}",
+            format_color_bold("39"),
+            symbols.git_has_stash,
+            util::capped_count(count, options.git_count_limit)
+        )),
+        Some(_) => Some(format!(
+            "{}{}{RESET_COLOR}",
+            format_color_bold("39"),
+            symbols.git_has_stash
+        )),
+    }
+}
+
+#[inline]
+fn format_ilsore_git_commit(
     head_info: &Option<structs::GitHeadInfo>,
-    file_status: &Option<structs::GitFileStatus>,
-    branch_ahead_behind: &Option<structs::GitBranchAheadBehind>,
+    options: &structs::ThemeOptions,
+) -> Option<String> {
+    let head_info = head_info.as_ref()?;
+    let age = head_info
+        .commit_time
+        .filter(|_| options.git_commit_age)
+        .map(|t| date_time::relative_age(t, chrono::Utc::now()));
+    let author = head_info
+        .commit_author
+        .as_deref()
+        .filter(|_| options.git_commit_author)
+        .map(util::initials);
+
+    let result = [age, author].into_iter().flatten().collect::<Vec<_>>();
+    (!result.is_empty())
+        .then(|| format!("{}{}{RESET_COLOR}", format_color("245"), result.join(" ")))
+}
+
+#[inline]
+fn format_ilsore_git_symbols(
+    data: &structs::GitOutputOptions,
     symbols: &structs::ThemeSymbols,
+    options: &structs::ThemeOptions,
 ) -> Option<String> {
-    let detached = head_info.as_ref().map_or(false, |b| b.detached);
-    let no_upstream = branch_ahead_behind.is_none();
-    let is_ahead = branch_ahead_behind.as_ref().map_or(false, |b| b.ahead > 0);
-    let is_behind = branch_ahead_behind.as_ref().map_or(false, |b| b.behind > 0);
-    let has_staged = file_status.as_ref().map_or(false, |b| b.staged);
-    let has_unstaged = file_status.as_ref().map_or(false, |b| b.unstaged);
-    let has_typechange = file_status.as_ref().map_or(false, |b| b.typechange);
-    let has_conflict = file_status.as_ref().map_or(false, |b| b.conflict);
-    let has_untracked = file_status.as_ref().map_or(false, |b| b.untracked);
+    let branch_ahead_behind = &data.branch_ahead_behind;
+    let detached = data.head_info.as_ref().is_some_and(|b| b.detached);
+    let unborn = data.head_info.as_ref().is_some_and(|b| b.unborn);
+    // Without finished lookup missing tracking branch is unknown, not absent
+    let no_upstream = data.upstream_checked && branch_ahead_behind.is_none();
+    let behind = branch_ahead_behind.as_ref().map_or(0, |b| b.behind);
+    let default_status = structs::GitFileStatus::default();
+    let status = data.file_status.as_ref().unwrap_or(&default_status);
+
+    // In triangular workflows commits are pushed to fork and pulled from upstream
+    let (ahead, ahead_symbol, behind_symbol) = match &data.push_ahead_behind {
+        Some(push) => (
+            push.ahead,
+            symbols.git_is_ahead_push,
+            symbols.git_is_behind_upstream,
+        ),
+        None => (
+            branch_ahead_behind.as_ref().map_or(0, |b| b.ahead),
+            symbols.git_is_ahead,
+            symbols.git_is_behind,
+        ),
+    };
+
+    let ahead_behind_symbols = match options.git_ahead_behind_counts {
+        false => vec![
+            symbol_bold(ahead > 0, ahead_symbol, "magenta"),
+            symbol_bold(behind > 0, behind_symbol, "green"),
+        ],
+        true => vec![
+            counter_bold(ahead, ahead_symbol, "magenta", options.git_count_limit),
+            counter_bold(behind, behind_symbol, "green", options.git_count_limit),
+        ],
+    };
 
     let detached_branch_symbols = vec![match (detached, no_upstream) {
         (true, _) => symbol_bold(true, symbols.git_branch_detached, "26"),
-        (false, true) => symbol_bold(true, symbols.git_has_no_upstream, "red"),
-        (false, false) => Some(
+        (false, _) if unborn => symbol_bold(true, symbols.git_branch_unborn, "208"),
+        (false, true) => Some(
             vec![
-                symbol_bold(is_ahead, symbols.git_is_ahead, "magenta"),
-                symbol_bold(is_behind, symbols.git_is_behind, "green"),
+                symbol_bold(true, symbols.git_has_no_upstream, "red"),
+                ahead_behind_symbols.i_join().into(),
             ]
             .i_join(),
         ),
+        (false, false) => Some(ahead_behind_symbols.i_join()),
     }];
 
-    let file_status_symbols = vec![
-        symbol_bold(has_staged, symbols.git_has_staged, "green"),
-        symbol_bold(has_unstaged, symbols.git_has_unstaged, "red"),
-        symbol_bold(has_typechange, symbols.git_has_typechange, "magenta"),
-        symbol_bold(has_conflict, symbols.git_has_conflict, "red"),
-        symbol(has_untracked, symbols.git_has_untracked, "magenta"),
-    ];
+    let file_status_symbols = match options.git_status_counts {
+        false => vec![
+            symbol_bold(status.staged > 0, symbols.git_has_staged, "green"),
+            symbol_bold(status.unstaged > 0, symbols.git_has_unstaged, "red"),
+            symbol_bold(status.typechange > 0, symbols.git_has_typechange, "magenta"),
+            symbol_bold(status.conflicted > 0, symbols.git_has_conflict, "red"),
+            symbol(status.untracked > 0, symbols.git_has_untracked, "magenta"),
+        ]
+        .i_join(),
+        true => [
+            counter_bold(
+                status.staged,
+                symbols.git_has_staged,
+                "green",
+                options.git_count_limit,
+            ),
+            counter_bold(
+                status.unstaged,
+                symbols.git_has_unstaged,
+                "red",
+                options.git_count_limit,
+            ),
+            counter_bold(
+                status.renamed,
+                symbols.git_has_renamed,
+                "yellow",
+                options.git_count_limit,
+            ),
+            counter_bold(
+                status.deleted,
+                symbols.git_has_deleted,
+                "red",
+                options.git_count_limit,
+            ),
+            counter_bold(
+                status.typechange,
+                symbols.git_has_typechange,
+                "magenta",
+                options.git_count_limit,
+            ),
+            counter_bold(
+                status.conflicted,
+                symbols.git_has_conflict,
+                "red",
+                options.git_count_limit,
+            ),
+            counter(
+                status.untracked,
+                symbols.git_has_untracked,
+                "magenta",
+                options.git_count_limit,
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" "),
+    };
 
-    let result_data = vec![
+    let status_unknown_symbol = symbol_bold(data.timed_out, symbols.git_status_unknown, "208");
+
+    let result_data = [
         detached_branch_symbols.i_join(),
-        file_status_symbols.i_join(),
+        file_status_symbols + status_unknown_symbol.as_deref().unwrap_or_default(),
     ];
 
     let result = result_data.join(" "); // TODO: spaces at the end
@@ -179,6 +514,42 @@ fn symbol_bold(present: bool, symbol: &'static str, color: &'static str) -> Opti
     }
 }
 
+#[inline]
+fn counter_bold(
+    count: usize,
+    symbol: &'static str,
+    color: &'static str,
+    limit: usize,
+) -> Option<String> {
+    match count {
+        0 => None,
+        _ => Some(format!(
+            "{}{}{}",
+            format_color_bold(color),
+            symbol,
+            util::capped_count(count, limit)
+        )),
+    }
+}
+
+#[inline]
+fn counter(
+    count: usize,
+    symbol: &'static str,
+    color: &'static str,
+    limit: usize,
+) -> Option<String> {
+    match count {
+        0 => None,
+        _ => Some(format!(
+            "{}{}{}",
+            format_color(color),
+            symbol,
+            util::capped_count(count, limit)
+        )),
+    }
+}
+
 #[inline]
 fn symbol(present: bool, symbol: &'static str, color: &'static str) -> Option<String> {
     match present {
@@ -193,6 +564,46 @@ trait Joiner {
 
 impl Joiner for Vec<Option<String>> {
     fn i_join(&self) -> String {
-        return self.iter().filter_map(|p| p.as_deref()).collect::<String>();
+        self.iter().filter_map(|p| p.as_deref()).collect::<String>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_ilsore_git, RESET_COLOR};
+    use crate::structs;
+
+    #[test]
+    fn format_ilsore_git_timed_out_test() {
+        let data = structs::GitOutputOptions {
+            head_info: None,
+            file_status: None,
+            branch_ahead_behind: None,
+            push_ahead_behind: None,
+            base_ahead_behind: None,
+            remote: None,
+            repo_state: None,
+            stash_count: None,
+            submodules: None,
+            diff_stats: None,
+            lfs: None,
+            timed_out: true,
+            upstream_checked: false,
+            repo_kind: structs::GitRepoKind::Main,
+            shallow: false,
+            partial_clone: false,
+            sparse_checkout: None,
+            worktree: None,
+        };
+
+        let result = format_ilsore_git(
+            &data,
+            &structs::ThemeSymbols::ascii(),
+            &structs::ThemeOptions::default(),
+        );
+        assert_eq!(
+            result,
+            format!("(%{{%F{{magenta}}%}}Git: %{{%B%F{{208}}%}}~{RESET_COLOR})")
+        );
     }
 }
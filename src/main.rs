@@ -2,24 +2,51 @@ use clap::Parser;
 use error::MapLog;
 use std::borrow::Cow;
 use std::thread;
+use std::time::Duration;
 
 mod args;
+mod daemon;
 mod date_time;
 mod error;
+mod git_cache;
+mod git_config;
+mod git_remote;
 mod git_utils;
 mod ilsore_format;
 mod ilsore_format_color;
 mod python_status;
 mod structs;
+#[cfg(test)]
+mod test_util;
 mod user_host;
 mod util;
 
 fn main() -> error::Result<()> {
     args::init_argument_parser();
-    let args = args::Args::parse();
+    let mut args = args::Args::parse();
 
     error::setup_errors(args.error_output);
-    let theme_data = theme_data(&args);
+
+    if let Some(args::Command::Daemon) = args.command {
+        return daemon::run(
+            &args.daemon_socket(),
+            Duration::from_millis(args.cache_ttl_ms),
+        );
+    }
+
+    let location = match args.disable_git {
+        true => None,
+        false => git_utils::discover_repo(args.git_start_folder.as_deref()).ok_or_log(),
+    };
+    if let Some(config) = location
+        .as_ref()
+        .and_then(|l| git_utils::repo_config(l).ok_or_log())
+    {
+        args.override_from_config(&config);
+    }
+    let location = location.filter(|_| !args.disable_git);
+
+    let theme_data = theme_data(&args, location.as_ref());
     let symbols = args.symbols();
 
     print!("{}", args.theme()(&theme_data, symbols));
@@ -27,7 +54,10 @@ fn main() -> error::Result<()> {
     Ok(())
 }
 
-fn theme_data(args: &args::Args) -> structs::ThemeData {
+fn theme_data(
+    args: &args::Args,
+    location: Option<&structs::GitRepoLocation>,
+) -> structs::ThemeData {
     let mut mut_hostname: Option<String> = None;
     let mut git_info: Option<structs::GitOutputOptions> = None;
 
@@ -40,16 +70,27 @@ fn theme_data(args: &args::Args) -> structs::ThemeData {
         .or_else(|| std::env::var("COMPUTERNAME").map(Cow::from).ok_or_log()); // windows
 
     let git_info_options = structs::GetGitInfoOptions {
-        start_folder: &args.git_start_folder,
         reference_name: args.git_reference.as_deref().unwrap_or("HEAD"),
+        describe_pattern: args.git_describe_pattern.as_deref(),
+        abbrev: args.git_abbrev,
+        self_hosted_pattern: args.git_self_hosted_pattern.as_deref(),
+        base_reference: args.git_base_reference.as_deref(),
         include_submodules: args.git_include_submodules,
-        include_untracked: !args.git_exclude_untracked,
+        untracked_files: match args.git_exclude_untracked {
+            true => Some(structs::UntrackedFiles::No),
+            false => args.git_untracked_files,
+        },
         refresh_status: args.git_refresh_status,
         include_ahead_behind: !args.git_exclude_ahead_behind,
         include_workdir_stats: !args.git_exclude_workdir_stats,
+        include_diff_stats: args.git_include_diff_stats,
+        timeout_ms: args.git_timeout_ms,
+        daemon_socket: (!args.disable_daemon).then(|| args.daemon_socket()),
+        use_cache: !args.no_cache,
+        cache_ttl_ms: args.cache_ttl_ms,
     };
 
-    if fast_hostname.is_none() || !args.disable_git {
+    if fast_hostname.is_none() || location.is_some() {
         thread::scope(|s| {
             s.spawn(|| {
                 if fast_hostname.is_none() {
@@ -58,8 +99,8 @@ fn theme_data(args: &args::Args) -> structs::ThemeData {
             });
 
             s.spawn(|| {
-                if !args.disable_git {
-                    git_info = git_utils::process_current_dir(&git_info_options).ok_or_log();
+                if let Some(location) = location {
+                    git_info = git_utils::process_location(location, &git_info_options).ok_or_log();
                 }
             });
         });
@@ -74,5 +115,17 @@ fn theme_data(args: &args::Args) -> structs::ThemeData {
         username: user_host::username(),
         python: python_status::python_info(),
         git: git_info,
+        options: structs::ThemeOptions {
+            git_stash_count: args.git_stash_count,
+            git_status_counts: args.git_status_counts,
+            git_ahead_behind_counts: args.git_ahead_behind_counts,
+            git_count_limit: args.git_count_limit,
+            git_reference_display: args.git_reference_display,
+            git_reference_max_length: args.git_reference_max_length,
+            git_commit_age: args.git_commit_age,
+            git_commit_author: args.git_commit_author,
+            git_remote: args.git_remote,
+            git_remote_slug: args.git_remote_slug,
+        },
     }
 }
@@ -1,19 +1,31 @@
 use std::path;
 
+use crate::util::LastPart;
+
 /// Options for git status reporter
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct GetGitInfoOptions<'a> {
-    /// Start forlder. None value means current folder
-    pub start_folder: &'a Option<path::PathBuf>,
-
     /// Reference name to ask information for
     pub reference_name: &'a str,
 
+    /// Glob tags should match to describe detached HEAD. None value means all tags
+    pub describe_pattern: Option<&'a str>,
+
+    /// Minimal length of abbreviated commit hash. None value means `core.abbrev`
+    pub abbrev: Option<u64>,
+
+    /// Glob hosts of self-hosted git servers should match. None value means no such hosts
+    pub self_hosted_pattern: Option<&'a str>,
+
+    /// Base reference to compare with, "auto" means `origin/HEAD`. None value means no comparison
+    pub base_reference: Option<&'a str>,
+
     /// Flag if git status should include submodules information
     pub include_submodules: bool,
 
-    /// Flag if git status should include untracked files
-    pub include_untracked: bool,
+    /// How git status should report untracked files.
+    /// None value means `status.showUntrackedFiles` of the repository or its default
+    pub untracked_files: Option<UntrackedFiles>,
 
     /// Flag if git status should do soft refresh
     pub refresh_status: bool,
@@ -23,6 +35,52 @@ pub(crate) struct GetGitInfoOptions<'a> {
 
     /// Flag if git status should include workdir check
     pub include_workdir_stats: bool,
+
+    /// Flag if numbers of inserted and deleted lines should be computed
+    pub include_diff_stats: bool,
+
+    /// Time budget for git information in milliseconds. None value means no limit
+    pub timeout_ms: Option<u64>,
+
+    /// Socket of cache daemon to ask first. None value means direct computation only
+    pub daemon_socket: Option<path::PathBuf>,
+
+    /// Flag if file status and ahead/behind information may be reused from cache folder
+    pub use_cache: bool,
+
+    /// How long cached information is reused in milliseconds
+    pub cache_ttl_ms: u64,
+}
+
+/// Modes of `status.showUntrackedFiles`
+#[derive(
+    clap::ValueEnum,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[clap(rename_all = "kebab_case")]
+pub(crate) enum UntrackedFiles {
+    /// Don't show untracked files
+    No,
+    /// Show untracked files and directories without looking inside them
+    #[default]
+    Normal,
+    /// Show every file inside untracked directories
+    All,
+}
+
+/// Repository found by discovery
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitRepoLocation {
+    pub git_dir: path::PathBuf,
+    /// None value means bare repository
+    pub work_dir: Option<path::PathBuf>,
 }
 
 /// Data to be passed to theme processor
@@ -33,6 +91,54 @@ pub(crate) struct ThemeData {
     pub username: Option<String>,
     pub python: Option<String>,
     pub git: Option<GitOutputOptions>,
+    pub options: ThemeOptions,
+}
+
+/// Display options passed to theme processor
+#[derive(Debug, Default)]
+pub(crate) struct ThemeOptions {
+    /// Flag if number of stashes should be shown next to stash symbol
+    pub git_stash_count: bool,
+
+    /// Flag if number of files should be shown next to file status symbols
+    pub git_status_counts: bool,
+
+    /// Flag if number of commits should be shown next to ahead/behind symbols
+    pub git_ahead_behind_counts: bool,
+
+    /// Largest number shown in full, bigger numbers are shown as "limit+"
+    pub git_count_limit: usize,
+
+    /// Which part of reference name is shown
+    pub git_reference_display: ReferenceDisplay,
+
+    /// Longer reference names are shortened in the middle. None value means no limit
+    pub git_reference_max_length: Option<usize>,
+
+    /// Flag if time passed since HEAD commit should be shown
+    pub git_commit_age: bool,
+
+    /// Flag if initials of HEAD commit author should be shown
+    pub git_commit_author: bool,
+
+    /// Flag if hosting provider of remote should be shown
+    pub git_remote: bool,
+
+    /// Flag if "owner/repo" of remote should be shown next to provider
+    pub git_remote_slug: bool,
+}
+
+/// Form of reference name shown by theme
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default)]
+#[clap(rename_all = "kebab_case")]
+pub(crate) enum ReferenceDisplay {
+    /// "refs/heads/feature/login"
+    Full,
+    /// "feature/login"
+    #[default]
+    Short,
+    /// "login"
+    Last,
 }
 
 #[derive(Debug)]
@@ -40,20 +146,148 @@ pub(crate) struct ThemeSymbols {
     pub git_branch: &'static str,
     pub git_has_no_upstream: &'static str,
     pub git_branch_detached: &'static str,
+    pub git_branch_unborn: &'static str,
+    pub git_reference_ellipsis: &'static str,
     pub git_is_ahead: &'static str,
     pub git_is_behind: &'static str,
+    pub git_is_ahead_push: &'static str,
+    pub git_is_behind_upstream: &'static str,
+    pub git_base: &'static str,
     pub git_has_conflict: &'static str,
     pub git_has_untracked: &'static str,
     pub git_has_typechange: &'static str,
     pub git_has_unstaged: &'static str,
     pub git_has_staged: &'static str,
+    pub git_has_renamed: &'static str,
+    pub git_has_deleted: &'static str,
+    pub git_has_stash: &'static str,
+    pub git_submodule_uninitialized: &'static str,
+    pub git_submodule_out_of_date: &'static str,
+    pub git_submodule_dirty: &'static str,
+    pub git_lfs_pointer: &'static str,
+    pub git_lfs_lock: &'static str,
+    pub git_diff_insertions: &'static str,
+    pub git_diff_deletions: &'static str,
+    pub git_remote_github: &'static str,
+    pub git_remote_gitlab: &'static str,
+    pub git_remote_bitbucket: &'static str,
+    pub git_remote_gitea: &'static str,
+    pub git_remote_azure_devops: &'static str,
+    pub git_remote_self_hosted: &'static str,
+    pub git_remote_local: &'static str,
+    pub git_remote_unknown: &'static str,
+    pub git_status_unknown: &'static str,
+    pub git_repo_worktree: &'static str,
+    pub git_repo_submodule: &'static str,
+    pub git_repo_bare: &'static str,
+    pub git_repo_shallow: &'static str,
+    pub git_repo_partial_clone: &'static str,
+    pub git_repo_sparse: &'static str,
+    pub git_state_merge: &'static str,
+    pub git_state_revert: &'static str,
+    pub git_state_cherry_pick: &'static str,
+    pub git_state_bisect: &'static str,
+    pub git_state_rebase: &'static str,
+    pub git_state_rebase_interactive: &'static str,
+    pub git_state_rebase_merge: &'static str,
+    pub git_state_apply_mailbox: &'static str,
+    pub git_state_apply_mailbox_or_rebase: &'static str,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct GitOutputOptions {
     pub head_info: Option<GitHeadInfo>,
     pub file_status: Option<GitFileStatus>,
     pub branch_ahead_behind: Option<GitBranchAheadBehind>,
+    /// Ahead/behind against push branch. None value if it's the same as tracking branch
+    pub push_ahead_behind: Option<GitBranchAheadBehind>,
+    pub base_ahead_behind: Option<GitBaseAheadBehind>,
+    /// Hosting of tracked remote. None value if there is no remote
+    pub remote: Option<GitRemote>,
+    pub repo_state: Option<GitRepoState>,
+    pub stash_count: Option<usize>,
+    /// None value if submodules aren't included or there are none
+    pub submodules: Option<GitSubmoduleSummary>,
+    /// Uncommitted line changes against HEAD. None value if diff stats aren't included
+    pub diff_stats: Option<GitDiffStats>,
+    /// None value if the repository doesn't use Git LFS
+    pub lfs: Option<GitLfsStatus>,
+    /// Flag if time budget ran out before all information was collected
+    pub timed_out: bool,
+    /// Flag if tracking branch was looked up, None value of `branch_ahead_behind` means
+    /// there is no upstream only then
+    pub upstream_checked: bool,
+    pub repo_kind: GitRepoKind,
+    /// History is truncated, ahead/behind counts aren't computed, only tracking branch is checked
+    pub shallow: bool,
+    /// Objects are fetched from promisor remote on demand
+    pub partial_clone: bool,
+    /// None value if the whole tree is checked out
+    pub sparse_checkout: Option<GitSparseCheckout>,
+    /// None value unless `repo_kind` is a linked worktree
+    pub worktree: Option<GitWorktree>,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitWorktree {
+    /// Name given by `git worktree add`
+    pub name: Option<String>,
+}
+
+/// Number of submodules needing attention
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitSubmoduleSummary {
+    /// Registered but never checked out, `git submodule update --init` is needed
+    pub uninitialized: usize,
+    /// Checked out commit differs from the one recorded in superproject
+    pub out_of_date: usize,
+    /// Submodule has changed or untracked files
+    pub dirty: usize,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitRemote {
+    pub provider: GitProvider,
+    /// "owner/repo" part of remote URL. None value for local repositories
+    pub slug: Option<String>,
+}
+
+/// Hosting provider recognized by remote URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum GitProvider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    /// Gitea and Forgejo instances like Codeberg
+    Gitea,
+    AzureDevOps,
+    /// Host matched by self-hosted pattern
+    SelfHosted,
+    /// Remote is a path on local file system
+    Local,
+    Unknown,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitSparseCheckout {
+    /// Patterns are directories, as `git sparse-checkout set --cone` writes them
+    pub cone: bool,
+    /// Number of directories in cone mode, number of patterns otherwise
+    pub patterns: usize,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitLfsStatus {
+    /// LFS files checked out as pointers, `git lfs pull` is needed
+    pub pointers: usize,
+    /// Locks held by current user
+    pub locks: usize,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitDiffStats {
+    pub insertions: usize,
+    pub deletions: usize,
 }
 
 pub(crate) struct DateTime {
@@ -61,40 +295,212 @@ pub(crate) struct DateTime {
     pub time: Box<dyn std::fmt::Display>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct GitHeadInfo {
+    /// Full reference name, e.g. "refs/heads/feature/login"
+    pub reference_name: Option<String>,
+    /// Reference shorthand, e.g. "feature/login"
     pub reference_short: Option<String>,
     pub oid_short: Option<String>,
     pub detached: bool,
+    /// Branch has no commits yet
+    pub unborn: bool,
+    /// Nearest tag, computed for detached HEAD only
+    pub describe: Option<GitDescribe>,
+    /// Commit time in seconds since Unix epoch
+    pub commit_time: Option<i64>,
+    pub commit_author: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitDescribe {
+    pub tag: String,
+    /// Number of commits since the tag
+    pub distance: usize,
+}
+
+impl std::fmt::Display for GitDescribe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.distance {
+            0 => write!(f, "{}", self.tag),
+            distance => write!(f, "{}+{}", self.tag, distance),
+        }
+    }
+}
+
+/// Number of files in each status category
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub(crate) struct GitFileStatus {
-    pub conflict: bool,
-    pub untracked: bool,
-    pub typechange: bool,
-    pub unstaged: bool,
-    pub staged: bool,
+    pub conflicted: usize,
+    pub untracked: usize,
+    pub typechange: usize,
+    pub unstaged: usize,
+    pub staged: usize,
+    pub renamed: usize,
+    pub deleted: usize,
+    pub conflicts: GitConflicts,
 }
 
-#[derive(Debug)]
+/// Number of conflicted files by the kind of conflict, as `git status --short` shows them
+#[derive(Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitConflicts {
+    /// UU
+    pub both_modified: usize,
+    /// AA
+    pub both_added: usize,
+    /// DD
+    pub both_deleted: usize,
+    /// AU
+    pub added_by_us: usize,
+    /// UA
+    pub added_by_them: usize,
+    /// DU
+    pub deleted_by_us: usize,
+    /// UD
+    pub deleted_by_them: usize,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub(crate) struct GitBranchAheadBehind {
     pub ahead: usize,
     pub behind: usize,
 }
+/// Kind of checkout the prompt is shown for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum GitRepoKind {
+    Main,
+    LinkedWorktree,
+    Submodule,
+    Bare,
+}
+
+/// Operation in progress in the repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum GitOperation {
+    Merge,
+    Revert,
+    CherryPick,
+    Bisect,
+    Rebase,
+    RebaseInteractive,
+    RebaseMerge,
+    ApplyMailbox,
+    ApplyMailboxOrRebase,
+}
+
+/// Ahead/behind information against project base branch
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitBaseAheadBehind {
+    pub reference_short: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct GitRepoState {
+    pub operation: GitOperation,
+    /// Current step and total number of steps, if the operation has any
+    pub progress: Option<(usize, usize)>,
+}
+
+impl GitHeadInfo {
+    pub(crate) fn reference_display(&self, display: ReferenceDisplay) -> Option<&str> {
+        match display {
+            ReferenceDisplay::Full => self.reference_name.as_deref(),
+            ReferenceDisplay::Short => self.reference_short.as_deref(),
+            ReferenceDisplay::Last => self.reference_short.as_deref().map(|r| r.last_part()),
+        }
+    }
+}
+
 impl ThemeSymbols {
+    pub(crate) fn git_operation(&self, operation: GitOperation) -> &'static str {
+        match operation {
+            GitOperation::Merge => self.git_state_merge,
+            GitOperation::Revert => self.git_state_revert,
+            GitOperation::CherryPick => self.git_state_cherry_pick,
+            GitOperation::Bisect => self.git_state_bisect,
+            GitOperation::Rebase => self.git_state_rebase,
+            GitOperation::RebaseInteractive => self.git_state_rebase_interactive,
+            GitOperation::RebaseMerge => self.git_state_rebase_merge,
+            GitOperation::ApplyMailbox => self.git_state_apply_mailbox,
+            GitOperation::ApplyMailboxOrRebase => self.git_state_apply_mailbox_or_rebase,
+        }
+    }
+
+    pub(crate) fn git_provider(&self, provider: GitProvider) -> &'static str {
+        match provider {
+            GitProvider::GitHub => self.git_remote_github,
+            GitProvider::GitLab => self.git_remote_gitlab,
+            GitProvider::Bitbucket => self.git_remote_bitbucket,
+            GitProvider::Gitea => self.git_remote_gitea,
+            GitProvider::AzureDevOps => self.git_remote_azure_devops,
+            GitProvider::SelfHosted => self.git_remote_self_hosted,
+            GitProvider::Local => self.git_remote_local,
+            GitProvider::Unknown => self.git_remote_unknown,
+        }
+    }
+
+    pub(crate) fn git_repo_kind(&self, kind: GitRepoKind) -> Option<&'static str> {
+        match kind {
+            GitRepoKind::Main => None,
+            GitRepoKind::LinkedWorktree => Some(self.git_repo_worktree),
+            GitRepoKind::Submodule => Some(self.git_repo_submodule),
+            GitRepoKind::Bare => Some(self.git_repo_bare),
+        }
+    }
+
     pub(crate) fn utf8_power() -> Self {
         ThemeSymbols {
             git_branch: "\u{e0a0}",          // 
             git_has_no_upstream: "\u{25B2}", // ▲
             git_branch_detached: "\u{2630}", // ☰
+            git_branch_unborn: "\u{2205}",   // ∅
+            git_reference_ellipsis: "…",
             git_is_ahead: "↑",
             git_is_behind: "↓",
+            git_is_ahead_push: "⇡",
+            git_is_behind_upstream: "⇣",
+            git_base: "⌂",
             git_has_conflict: "✘",
             git_has_untracked: "?",
             git_has_typechange: "‡",
             git_has_unstaged: "●",
             git_has_staged: "●",
+            git_has_renamed: "»",
+            git_has_deleted: "✖",
+            git_has_stash: "≡",
+            git_submodule_uninitialized: "\u{25CC}", // ◌
+            git_submodule_out_of_date: "\u{27F3}",   // ⟳
+            git_submodule_dirty: "\u{270E}",         // ✎
+            git_lfs_pointer: "\u{25C7}",             // ◇
+            git_lfs_lock: "\u{26BF}",                // ⚿
+            git_diff_insertions: "+",
+            git_diff_deletions: "\u{2212}",      // −
+            git_remote_github: "\u{F09B}",       // nf-fa-github
+            git_remote_gitlab: "\u{F296}",       // nf-fa-gitlab
+            git_remote_bitbucket: "\u{F171}",    // nf-fa-bitbucket
+            git_remote_gitea: "\u{F339}",        // nf-linux-gitea
+            git_remote_azure_devops: "\u{EBE8}", // nf-cod-azure_devops
+            git_remote_self_hosted: "\u{F233}",  // nf-fa-server
+            git_remote_local: "\u{F07B}",        // nf-fa-folder
+            git_remote_unknown: "\u{F1D3}",      // nf-fa-git
+            git_status_unknown: "\u{231B}",      // ⌛
+            git_repo_worktree: "\u{2398}",       // ⎘
+            git_repo_submodule: "\u{229F}",      // ⊟
+            git_repo_bare: "\u{25CB}",           // ○
+            git_repo_shallow: "\u{2307}",        // ⌇
+            git_repo_partial_clone: "\u{25D0}",  // ◐
+            git_repo_sparse: "\u{2237}",         // ∷
+            git_state_merge: "MERGING",
+            git_state_revert: "REVERTING",
+            git_state_cherry_pick: "CHERRY-PICKING",
+            git_state_bisect: "BISECTING",
+            git_state_rebase: "REBASE",
+            git_state_rebase_interactive: "REBASE-i",
+            git_state_rebase_merge: "REBASE-m",
+            git_state_apply_mailbox: "AM",
+            git_state_apply_mailbox_or_rebase: "AM/REBASE",
         }
     }
     pub(crate) fn utf8() -> Self {
@@ -102,13 +508,52 @@ impl ThemeSymbols {
             git_branch: "ᚠ",
             git_has_no_upstream: "ᛘ",
             git_branch_detached: "\u{2630}", // ☰
+            git_branch_unborn: "\u{2205}",   // ∅
+            git_reference_ellipsis: "…",
             git_is_ahead: "↑",
             git_is_behind: "↓",
+            git_is_ahead_push: "⇡",
+            git_is_behind_upstream: "⇣",
+            git_base: "⌂",
             git_has_conflict: "✘",
             git_has_untracked: "?",
             git_has_typechange: "‡",
             git_has_unstaged: "●",
             git_has_staged: "●",
+            git_has_renamed: "»",
+            git_has_deleted: "✖",
+            git_has_stash: "≡",
+            git_submodule_uninitialized: "\u{25CC}", // ◌
+            git_submodule_out_of_date: "\u{27F3}",   // ⟳
+            git_submodule_dirty: "\u{270E}",         // ✎
+            git_lfs_pointer: "\u{25C7}",             // ◇
+            git_lfs_lock: "\u{26BF}",                // ⚿
+            git_diff_insertions: "+",
+            git_diff_deletions: "\u{2212}", // −
+            git_remote_github: "GH",
+            git_remote_gitlab: "GL",
+            git_remote_bitbucket: "BB",
+            git_remote_gitea: "GT",
+            git_remote_azure_devops: "AZ",
+            git_remote_self_hosted: "SH",
+            git_remote_local: "LO",
+            git_remote_unknown: "RE",
+            git_status_unknown: "…",
+            git_repo_worktree: "\u{2398}",      // ⎘
+            git_repo_submodule: "\u{229F}",     // ⊟
+            git_repo_bare: "\u{25CB}",          // ○
+            git_repo_shallow: "\u{2307}",       // ⌇
+            git_repo_partial_clone: "\u{25D0}", // ◐
+            git_repo_sparse: "\u{2237}",        // ∷
+            git_state_merge: "MERGING",
+            git_state_revert: "REVERTING",
+            git_state_cherry_pick: "CHERRY-PICKING",
+            git_state_bisect: "BISECTING",
+            git_state_rebase: "REBASE",
+            git_state_rebase_interactive: "REBASE-i",
+            git_state_rebase_merge: "REBASE-m",
+            git_state_apply_mailbox: "AM",
+            git_state_apply_mailbox_or_rebase: "AM/REBASE",
         }
     }
 
@@ -117,13 +562,52 @@ impl ThemeSymbols {
             git_branch: "",
             git_has_no_upstream: "&",
             git_branch_detached: "||",
+            git_branch_unborn: "0",
+            git_reference_ellipsis: "..",
             git_is_ahead: "^",
             git_is_behind: "v",
+            git_is_ahead_push: ">",
+            git_is_behind_upstream: "<",
+            git_base: "@",
             git_has_conflict: "x",
             git_has_untracked: "?",
             git_has_typechange: "T",
             git_has_unstaged: "*",
             git_has_staged: "*",
+            git_has_renamed: ">",
+            git_has_deleted: "-",
+            git_has_stash: "$",
+            git_submodule_uninitialized: "s_",
+            git_submodule_out_of_date: "s^",
+            git_submodule_dirty: "s*",
+            git_lfs_pointer: "P",
+            git_lfs_lock: "L",
+            git_diff_insertions: "+",
+            git_diff_deletions: "-",
+            git_remote_github: "GH",
+            git_remote_gitlab: "GL",
+            git_remote_bitbucket: "BB",
+            git_remote_gitea: "GT",
+            git_remote_azure_devops: "AZ",
+            git_remote_self_hosted: "SH",
+            git_remote_local: "LO",
+            git_remote_unknown: "RE",
+            git_status_unknown: "~",
+            git_repo_worktree: "W",
+            git_repo_submodule: "S",
+            git_repo_bare: "B",
+            git_repo_shallow: "=",
+            git_repo_partial_clone: ":",
+            git_repo_sparse: "#",
+            git_state_merge: "MERGING",
+            git_state_revert: "REVERTING",
+            git_state_cherry_pick: "CHERRY-PICKING",
+            git_state_bisect: "BISECTING",
+            git_state_rebase: "REBASE",
+            git_state_rebase_interactive: "REBASE-i",
+            git_state_rebase_merge: "REBASE-m",
+            git_state_apply_mailbox: "AM",
+            git_state_apply_mailbox_or_rebase: "AM/REBASE",
         }
     }
 }
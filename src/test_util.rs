@@ -0,0 +1,59 @@
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use crate::error;
+
+/// Empty folder of a single test, unique between tests and concurrent runs. Removed on drop
+pub(crate) struct TestDir(PathBuf);
+
+impl TestDir {
+    /// Sets up errors as well, `ok_or_log` used by tested code needs them
+    pub(crate) fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        error::setup_errors(false);
+
+        let path = tmp_root().join(format!(
+            "{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Leftover of a killed run with the same process id
+        if path.exists() {
+            std::fs::remove_dir_all(&path).unwrap();
+        }
+        std::fs::create_dir_all(&path).unwrap();
+        TestDir(path)
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Same folder integration tests get as `CARGO_TARGET_TMPDIR`, which isn't set for unit tests
+fn tmp_root() -> PathBuf {
+    // Unit test binary is built into "target/<profile>/deps"
+    let exe = std::env::current_exe().unwrap();
+    exe.ancestors().nth(3).unwrap().join("tmp")
+}
@@ -1,8 +1,50 @@
+use std::borrow::Cow;
+
 #[allow(dead_code)]
 pub(crate) fn print_type_of<T>(_: &T) {
     println!("{}", std::any::type_name::<T>())
 }
 
+/// Formats a counter, replacing counts above `limit` with "`limit`+"
+pub(crate) fn capped_count(count: usize, limit: usize) -> String {
+    match count > limit {
+        true => format!("{}+", limit),
+        false => count.to_string(),
+    }
+}
+
+/// Shortens `value` to `max_length` characters replacing its middle with `ellipsis`
+pub(crate) fn truncate_middle<'a>(
+    value: &'a str,
+    max_length: Option<usize>,
+    ellipsis: &str,
+) -> Cow<'a, str> {
+    let length = value.chars().count();
+    let Some(max_length) = max_length.filter(|&m| m < length) else {
+        return Cow::Borrowed(value);
+    };
+
+    let kept = max_length.saturating_sub(ellipsis.chars().count());
+    let tail = kept / 2;
+    let head = kept - tail;
+
+    value
+        .chars()
+        .take(head)
+        .chain(ellipsis.chars())
+        .chain(value.chars().skip(length - tail))
+        .collect::<String>()
+        .into()
+}
+
+/// Uppercase first letters of each word, e.g. "JD" for "John Doe"
+pub(crate) fn initials(name: &str) -> String {
+    name.split_whitespace()
+        .filter_map(|w| w.chars().next())
+        .flat_map(char::to_uppercase)
+        .collect()
+}
+
 pub(crate) trait LastPart {
     fn last_part(&self) -> &Self;
     fn last_two_parts(&self) -> &Self;
@@ -25,9 +67,43 @@ impl LastPart for str {
 
 #[cfg(test)]
 mod test {
-    use super::LastPart;
+    use super::{capped_count, initials, truncate_middle, LastPart};
     use rstest::rstest;
 
+    #[rstest]
+    #[case(0, 99, "0")]
+    #[case(3, 99, "3")]
+    #[case(99, 99, "99")]
+    #[case(100, 99, "99+")]
+    #[case(12345, 999, "999+")]
+    fn capped_count_test(#[case] count: usize, #[case] limit: usize, #[case] expected: &str) {
+        assert_eq!(capped_count(count, limit), expected);
+    }
+
+    #[rstest]
+    #[case("feature/login", None, "feature/login")]
+    #[case("feature/login", Some(13), "feature/login")]
+    #[case("feature/login", Some(9), "feat…ogin")]
+    #[case("feature/login", Some(8), "feat…gin")]
+    #[case("feature/login", Some(1), "…")]
+    #[case("ветка/длинная", Some(7), "вет…ная")]
+    fn truncate_middle_test(
+        #[case] value: &str,
+        #[case] max_length: Option<usize>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(truncate_middle(value, max_length, "…"), expected);
+    }
+
+    #[rstest]
+    #[case("John Doe", "JD")]
+    #[case("  ada   lovelace ", "AL")]
+    #[case("Ёжик", "Ё")]
+    #[case("", "")]
+    fn initials_test(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(initials(name), expected);
+    }
+
     #[rstest]
     #[case("", "")]
     #[case("/", "")]
@@ -26,3 +26,38 @@ pub fn tmp_for<P: AsRef<Path>>(id: P) -> std::io::Result<PathBuf> {
 
     Ok(p)
 }
+
+/// Runs the binary in `path` with a plain ascii theme isolated from user configuration
+pub fn run_in(path: &Path) -> std::io::Result<std::process::Output> {
+    std::process::Command::new(bin_path())
+        .args([
+            "--theme-name",
+            "ilsore-no-color",
+            "--theme-symbols",
+            "ascii",
+        ])
+        .args(["--disable-daemon", "--no-cache"])
+        .env("HOME", path)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env_remove("GIT_DIR")
+        .env_remove("GIT_WORK_TREE")
+        .current_dir(path)
+        .output()
+}
+
+/// Git part of the prompt, e.g. "(Git:  master &)"
+pub fn git_prompt(output: &std::process::Output) -> Option<String> {
+    let stdout = std::str::from_utf8(&output.stdout).ok()?;
+    let start = stdout.find("(Git:")?;
+    let end = start + stdout[start..].find(')')?;
+    Some(stdout[start..=end].to_string())
+}
+
+/// Fresh directory for a test, removing leftovers of previous runs
+pub fn clean_tmp_for<P: AsRef<Path>>(id: P) -> std::io::Result<PathBuf> {
+    let p = tmp_root().join(id);
+    if p.exists() {
+        std::fs::remove_dir_all(&p)?;
+    }
+    tmp_for(p)
+}
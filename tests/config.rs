@@ -0,0 +1,60 @@
+//! Options overridden by repository git config
+
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::*;
+
+fn unborn_repository(
+    id: &str,
+) -> Result<(std::path::PathBuf, git2::Repository), Box<dyn std::error::Error>> {
+    let path = clean_tmp_for(id)?;
+    let repo = git2::Repository::init(&path)?;
+    repo.set_head("refs/heads/feature/config")?;
+    Ok((path, repo))
+}
+
+#[test]
+fn typed_values_override_arguments() -> Result<(), Box<dyn std::error::Error>> {
+    let (path, repo) = unborn_repository("config-typed")?;
+    let mut config = repo.config()?;
+    config.set_str("ilsore-format.reference-display", "full")?;
+    config.set_i64("ilsore-format.reference-max-length", 12)?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(
+        git_prompt(&result).as_deref(),
+        Some("(Git:  refs/..onfig 0)")
+    );
+    Ok(())
+}
+
+#[test]
+fn disable_git() -> Result<(), Box<dyn std::error::Error>> {
+    let (path, repo) = unborn_repository("config-disable-git")?;
+    repo.config()?.set_bool("ilsore-format.disable-git", true)?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result), None);
+    Ok(())
+}
+
+#[test]
+fn invalid_value_is_ignored() -> Result<(), Box<dyn std::error::Error>> {
+    let (path, repo) = unborn_repository("config-invalid")?;
+    repo.config()?
+        .set_str("ilsore-format.reference-display", "middle")?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(
+        git_prompt(&result).as_deref(),
+        Some("(Git:  feature/config 0)")
+    );
+    Ok(())
+}
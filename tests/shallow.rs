@@ -0,0 +1,51 @@
+//! Shallow clones, whose history is cut by `.git/shallow`
+
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::*;
+
+/// Repository with a single commit marked as shallow boundary
+fn shallow_repository(
+    id: &str,
+) -> Result<(std::path::PathBuf, git2::Repository), Box<dyn std::error::Error>> {
+    let path = clean_tmp_for(id)?;
+    let repo = git2::Repository::init(&path)?;
+    repo.set_head("refs/heads/master")?;
+
+    let signature = git2::Signature::now("Test", "test@example.com")?;
+    let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+    let oid = repo.commit(Some("HEAD"), &signature, &signature, "initial", &tree, &[])?;
+    std::fs::write(repo.path().join("shallow"), format!("{}\n", oid))?;
+
+    drop(tree);
+    Ok((path, repo))
+}
+
+#[test]
+fn branch_without_upstream() -> Result<(), Box<dyn std::error::Error>> {
+    let (path, _repo) = shallow_repository("shallow-no-upstream")?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  master = &)"));
+    Ok(())
+}
+
+#[test]
+fn branch_with_upstream() -> Result<(), Box<dyn std::error::Error>> {
+    let (path, repo) = shallow_repository("shallow-upstream")?;
+    let oid = repo.head()?.target().ok_or("HEAD has no commit")?;
+    repo.remote("origin", "https://example.com/repo.git")?;
+    repo.reference("refs/remotes/origin/master", oid, true, "clone")?;
+    let mut config = repo.config()?;
+    config.set_str("branch.master.remote", "origin")?;
+    config.set_str("branch.master.merge", "refs/heads/master")?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  master = )"));
+    Ok(())
+}
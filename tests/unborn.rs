@@ -0,0 +1,76 @@
+//! Repositories where HEAD points to a branch without commits
+
+use pretty_assertions::assert_eq;
+
+mod common;
+use common::*;
+
+fn signature() -> Result<git2::Signature<'static>, git2::Error> {
+    git2::Signature::now("Test", "test@example.com")
+}
+
+#[test]
+fn empty_repository() -> Result<(), Box<dyn std::error::Error>> {
+    let path = clean_tmp_for("unborn-empty")?;
+    let repo = git2::Repository::init(&path)?;
+    repo.set_head("refs/heads/master")?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  master 0)"));
+    Ok(())
+}
+
+#[test]
+fn unborn_branch_with_staged_file() -> Result<(), Box<dyn std::error::Error>> {
+    let path = clean_tmp_for("unborn-staged")?;
+    let repo = git2::Repository::init(&path)?;
+    repo.set_head("refs/heads/trunk")?;
+
+    std::fs::write(path.join("file.txt"), "content")?;
+    let mut index = repo.index()?;
+    index.add_path(Path::new("file.txt"))?;
+    index.write()?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  trunk 0*)"));
+    Ok(())
+}
+
+#[test]
+fn orphan_branch() -> Result<(), Box<dyn std::error::Error>> {
+    let path = clean_tmp_for("unborn-orphan")?;
+    let repo = git2::Repository::init(&path)?;
+    repo.set_head("refs/heads/master")?;
+
+    std::fs::write(path.join("file.txt"), "content")?;
+    let mut index = repo.index()?;
+    index.add_path(Path::new("file.txt"))?;
+    index.write()?;
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    repo.commit(
+        Some("HEAD"),
+        &signature()?,
+        &signature()?,
+        "initial",
+        &tree,
+        &[],
+    )?;
+
+    // Branch with commits and no upstream
+    let result = run_in(&path)?;
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  master &)"));
+
+    // Same as `git checkout --orphan gh-pages`, index keeps committed files as staged
+    repo.set_head("refs/heads/gh-pages")?;
+
+    let result = run_in(&path)?;
+
+    assert!(result.status.success());
+    assert_eq!(git_prompt(&result).as_deref(), Some("(Git:  gh-pages 0*)"));
+    Ok(())
+}